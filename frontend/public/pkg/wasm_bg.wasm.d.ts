@@ -1,29 +0,0 @@
-/* tslint:disable */
-/* eslint-disable */
-export const memory: WebAssembly.Memory;
-export function __wbg_teststruct_free(a: number): void;
-export function teststruct_new_async(a: number, b: number): number;
-export function teststruct_new_from_buffer(a: number, b: number): void;
-export function teststruct_get_image_data(a: number, b: number): void;
-export function teststruct_try_epoch(a: number, b: number, c: number): number;
-export function teststruct_get_target_width(a: number): number;
-export function teststruct_get_target_height(a: number): number;
-export function __wbg_randomcircle_free(a: number): void;
-export function __wbg_get_randomcircle_imgx(a: number): number;
-export function __wbg_set_randomcircle_imgx(a: number, b: number): void;
-export function __wbg_get_randomcircle_imgy(a: number): number;
-export function __wbg_set_randomcircle_imgy(a: number, b: number): void;
-export function __wbg_get_randomcircle_radius(a: number): number;
-export function __wbg_set_randomcircle_radius(a: number, b: number): void;
-export function randomcircle_center(a: number): number;
-export function randomcircle_set_center(a: number, b: number, c: number): void;
-export function randomcircle_color(a: number): number;
-export function randomcircle_set_color(a: number, b: number, c: number): void;
-export function __wbindgen_malloc(a: number): number;
-export function __wbindgen_realloc(a: number, b: number, c: number): number;
-export const __wbindgen_export_2: WebAssembly.Table;
-export function _dyn_core__ops__function__FnMut__A____Output___R_as_wasm_bindgen__closure__WasmClosure___describe__invoke__h221776f7705981c4(a: number, b: number, c: number): void;
-export function __wbindgen_add_to_stack_pointer(a: number): number;
-export function __wbindgen_exn_store(a: number): void;
-export function wasm_bindgen__convert__closures__invoke2_mut__hc0367a7ee78ffcaa(a: number, b: number, c: number, d: number): void;
-export function __wbindgen_free(a: number, b: number): void;
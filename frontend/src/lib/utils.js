@@ -1,19 +1,3 @@
-function fileToArrayBuffer(file) {
-    return new Promise((resolve, reject) => {
-        let fileReader = new FileReader();
-
-        fileReader.onerror = (event) => {
-            reject(event.target.error);
-        };
-
-        fileReader.onload = (event) => {
-            resolve(event.target.result);
-        };
-
-        fileReader.readAsArrayBuffer(file);
-    });
-}
-
 function arrayBufferToBase64( buffer ) {
     var binary = '';
     var bytes = new Uint8Array( buffer );
@@ -24,4 +8,13 @@ function arrayBufferToBase64( buffer ) {
     return window.btoa( binary );
 }
 
-module.exports = { fileToArrayBuffer, arrayBufferToBase64 };
+function base64ToArrayBuffer( base64 ) {
+    const binary = window.atob( base64 );
+    const bytes = new Uint8Array( binary.length );
+    for (let i = 0; i < binary.length; i++) {
+        bytes[ i ] = binary.charCodeAt( i );
+    }
+    return bytes.buffer;
+}
+
+module.exports = { arrayBufferToBase64, base64ToArrayBuffer };
@@ -2,11 +2,21 @@
 // global scope. We import the generated JS file to make `wasm_bindgen`
 // available which we need to initialize our WASM code.
 importScripts('../pkg/wasm.js');
-const { TestStruct } = wasm_bindgen;
+const { Session } = wasm_bindgen;
 
 
 
-let test_struct;
+let session;
+
+// Replaces the current session and reports its size and existing circles to the main thread.
+function setSession(new_session) {
+    session?.free();
+    session = new_session;
+    self.postMessage({
+        type: "init/done",
+        payload: [session.width, session.height, session.shapes()]
+    });
+}
 
 async function init_wasm_in_worker() {
     console.log("Initializing worker")
@@ -19,30 +29,52 @@ async function init_wasm_in_worker() {
 
         switch (type) {
             case "init/url":
-                const url = payload;
-                await TestStruct.new_async(url)
-                    .then(struct => {
+                const { url, options: url_options } = payload;
+                await Session.fromUrl(url, url_options)
+                    .then(new_session => {
                         console.log("loaded async!");
-                        test_struct = struct;
-                        self.postMessage({
-                            type: "init/done",
-                            payload: [test_struct.get_target_width(), test_struct.get_target_height()]
-                        });
+                        setSession(new_session);
                     })
                     .catch(err => {
                         console.error(err);
-                        self.postMessage({ type: "init/error" });
+                        self.postMessage({ type: "init/error", payload: err });
                     });
                 break;
-            case "init/buffer":
-                const buffer = payload;
+            case "run":
+                const { budget_ms, history } = payload;
+                // The main thread waits for run/done before starting another run, so it is sent
+                // even if the run failed.
                 try {
-                    test_struct = TestStruct.new_from_buffer(buffer);
-                    console.log("loaded from buffer!");
+                    const circles = session.runFor(budget_ms);
                     self.postMessage({
-                        type: "init/done",
-                        payload: [test_struct.get_target_width(), test_struct.get_target_height()]
+                        type: "run/done",
+                        payload: { circles, history },
                     });
+                } catch (error) {
+                    self.postMessage({
+                        type: "run/done",
+                        payload: { circles: [], history, error },
+                    });
+                }
+                break;
+            case "truncate":
+                session.truncate(payload);
+                break;
+            case "init/blob":
+                const { blob, options: blob_options } = payload;
+                await Session.fromBlob(blob, blob_options)
+                    .then(setSession)
+                    .catch(error => {
+                        self.postMessage({
+                            type: "init/error",
+                            payload: error
+                        });
+                    });
+                break;
+            case "init/image-data":
+                const { image_data, options: image_data_options } = payload;
+                try {
+                    setSession(Session.fromImageData(image_data, image_data_options));
                 } catch (error) {
                     self.postMessage({
                         type: "init/error",
@@ -50,30 +82,19 @@ async function init_wasm_in_worker() {
                     });
                 }
                 break;
-            case "epoch":
-                const { gen_size, num_gens } = payload;
-                let best_circle = test_struct.try_epoch(gen_size, num_gens);
-
-                let outbound_payload;
-                if (best_circle) {
-                    outbound_payload = { 
-                        circle: {
-                            imgx: best_circle.imgx,
-                            imgy: best_circle.imgy,
-                            center: best_circle.center,
-                            radius: best_circle.radius,
-                            color: best_circle.color,
-                        }
-                    };
-                } else {
-                    outbound_payload = undefined;
+            case "init/state":
+                try {
+                    setSession(Session.importState(new Uint8Array(payload)));
+                } catch (error) {
+                    self.postMessage({
+                        type: "init/error",
+                        payload: error
+                    });
                 }
-
-                // Send response back to be handled by callback in main thread.
-                self.postMessage({
-                    type: "epoch/done",
-                    payload: outbound_payload,
-                });
+                break;
+            case "export":
+                const state = session.exportState();
+                self.postMessage({ type: "export/done", payload: state }, [state.buffer]);
                 break;
             default:
                 console.error(`action type '${type}' not recognized`);
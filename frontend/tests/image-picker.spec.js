@@ -4,7 +4,6 @@
 import ImagePicker from '../src/lib/ImagePicker.svelte';
 import { render, fireEvent, waitFor } from '@testing-library/svelte';
 import userEvent from '@testing-library/user-event';
-import { fileToArrayBuffer } from '../src/lib/utils.js';
 
 test('file and answer inputs work', async () => {
     const user = userEvent.setup();
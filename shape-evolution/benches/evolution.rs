@@ -0,0 +1,142 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use image::{Rgba, RgbaImage};
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use shape_evolution::evolve::{epoch, sort_generation};
+use shape_evolution::image_diff::image_diff;
+use shape_evolution::mutate::Mutate;
+use shape_evolution::random_shape::{RandomCircle, RandomShape, ShapeOptions};
+use shape_evolution::span::{span_diff, span_diff_scalar};
+use std::iter;
+
+// A synthetic target with smooth gradients and some hard edges, so that benchmarks don't depend
+// on image files being present.
+fn target(imgx: u32, imgy: u32) -> RgbaImage {
+    RgbaImage::from_fn(imgx, imgy, |x, y| {
+        let stripe = if (x / 16 + y / 16) % 2 == 0 { 60 } else { 0 };
+        Rgba([
+            (x * 255 / imgx) as u8,
+            (y * 255 / imgy) as u8,
+            (((x + y) * 255 / (imgx + imgy)) as u8).saturating_add(stripe),
+            255,
+        ])
+    })
+}
+
+fn current(imgx: u32, imgy: u32) -> RgbaImage {
+    RgbaImage::from_pixel(imgx, imgy, Rgba([0, 0, 0, 255]))
+}
+
+// A population of 100 random circles with the given radius. The circles are the same on every
+// run, so that Criterion compares runs with the same workload.
+fn generation(radius: i32, imgx: u32, imgy: u32) -> Vec<RandomCircle> {
+    let options = ShapeOptions::default();
+    let mut rng = ChaCha8Rng::seed_from_u64(0);
+    iter::repeat_with(|| RandomCircle::new_with_rng(imgx, imgy, &options, &mut rng))
+        .map(|shape| RandomCircle { radius, ..shape })
+        .take(100)
+        .collect()
+}
+
+fn bench_score(c: &mut Criterion) {
+    let (imgx, imgy) = (400, 300);
+    let target_img = target(imgx, imgy);
+    let current_img = current(imgx, imgy);
+
+    let mut group = c.benchmark_group("score");
+    for radius in [1, 5, 25, 75, 200] {
+        let shapes = generation(radius, imgx, imgy);
+        group.bench_with_input(BenchmarkId::new("radius", radius), &shapes, |b, shapes| {
+            b.iter(|| {
+                for shape in shapes {
+                    black_box(shape.score(&target_img, &current_img));
+                }
+            });
+        });
+    }
+    group.bench_function("sort_generation", |b| {
+        let shapes = generation(25, imgx, imgy);
+        b.iter(|| sort_generation(&target_img, &current_img, shapes.clone()));
+    });
+    group.finish();
+}
+
+fn bench_span(c: &mut Criterion) {
+    let (imgx, imgy) = (400, 2);
+    let target_img = target(imgx, imgy);
+    let current_img = current(imgx, imgy);
+    let (target_span, current_span) = (&target_img.as_raw()[..], &current_img.as_raw()[..]);
+    let color = Rgba([90, 120, 30, 255]);
+
+    let mut group = c.benchmark_group("span");
+    group.bench_function("scalar", |b| {
+        b.iter(|| span_diff_scalar(black_box(target_span), black_box(current_span), color));
+    });
+    group.bench_function("vectorized", |b| {
+        b.iter(|| span_diff(black_box(target_span), black_box(current_span), color));
+    });
+    group.finish();
+}
+
+fn bench_image_diff(c: &mut Criterion) {
+    let mut group = c.benchmark_group("image_diff");
+    for (imgx, imgy) in [(200, 150), (800, 600)] {
+        let target_img = target(imgx, imgy);
+        let current_img = current(imgx, imgy);
+        group.bench_function(BenchmarkId::from_parameter(format!("{}x{}", imgx, imgy)), |b| {
+            b.iter(|| image_diff(&target_img, &current_img));
+        });
+    }
+    group.finish();
+}
+
+fn bench_mutate(c: &mut Criterion) {
+    let (imgx, imgy) = (400, 300);
+    let options = ShapeOptions::default();
+    let shapes = generation(25, imgx, imgy);
+    let mut rng = ChaCha8Rng::seed_from_u64(0);
+
+    c.bench_function("mutate", |b| {
+        b.iter(|| {
+            for shape in &shapes {
+                black_box(shape.mutate(&mut rng, 0.5, &options));
+            }
+        });
+    });
+}
+
+fn bench_epoch(c: &mut Criterion) {
+    let (imgx, imgy) = (200, 150);
+    let target_img = target(imgx, imgy);
+    let current_img = current(imgx, imgy);
+    let current_score = image_diff(&target_img, &current_img);
+    let options = ShapeOptions::default();
+    let mut rng = ChaCha8Rng::seed_from_u64(0);
+
+    let mut group = c.benchmark_group("epoch");
+    group.sample_size(10);
+    for shapes_per_epoch in [1, 4] {
+        group.bench_with_input(
+            BenchmarkId::new("shapes_per_epoch", shapes_per_epoch),
+            &shapes_per_epoch,
+            |b, &shapes_per_epoch| {
+                b.iter(|| {
+                    epoch(
+                        100,
+                        20,
+                        shapes_per_epoch,
+                        &target_img,
+                        &current_img,
+                        current_score,
+                        &options,
+                        &mut rng,
+                    )
+                });
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_score, bench_span, bench_image_diff, bench_mutate, bench_epoch);
+criterion_main!(benches);
@@ -0,0 +1,329 @@
+use crate::evolve::{evolve, EvolveOptions};
+use crate::output::OutputOptions;
+use crate::progress::Event;
+use std::fs;
+use std::io;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+// Settings for processing a whole directory of images.
+#[derive(Clone, Debug)]
+pub struct BatchOptions {
+    // Only images whose path relative to the input directory matches this pattern are processed.
+    pub pattern: glob::Pattern,
+    // Number of images processed in parallel.
+    pub jobs: usize,
+    // Process images again even if their output folder already holds a final image.
+    pub overwrite: bool,
+}
+
+#[derive(Debug)]
+pub enum Outcome {
+    Finished {
+        num_shapes: u32,
+        score_per_pixel: f64,
+        elapsed: Duration,
+    },
+    Skipped,
+    Failed(String),
+}
+
+#[derive(Debug)]
+pub struct BatchResult {
+    // Path of the image relative to the input directory.
+    pub image: PathBuf,
+    pub output_folder: PathBuf,
+    pub outcome: Outcome,
+}
+
+// Recursively finds all images under input_dir whose relative path matches the pattern. Returns
+// paths relative to input_dir in sorted order. Symbolic links to directories are not followed, so
+// a link back up the tree cannot make the search run forever. The skip directory, if it exists,
+// is left out; it is meant for an output directory inside input_dir, whose results would
+// otherwise be picked up as new images.
+pub fn find_images(
+    input_dir: &Path,
+    pattern: &glob::Pattern,
+    skip: Option<&Path>,
+) -> io::Result<Vec<PathBuf>> {
+    let skip = skip.and_then(|dir| fs::canonicalize(dir).ok());
+    let mut images = Vec::new();
+    let mut dirs = vec![input_dir.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            if entry.file_type()?.is_dir() {
+                if skip.is_none() || fs::canonicalize(&path).ok() != skip {
+                    dirs.push(path);
+                }
+                continue;
+            }
+
+            let relative = path.strip_prefix(input_dir).unwrap().to_path_buf();
+            if image::ImageFormat::from_path(&path).is_ok() && pattern.matches_path(&relative) {
+                images.push(relative);
+            }
+        }
+    }
+    images.sort();
+    Ok(images)
+}
+
+// Each image gets its own output folder, mirroring its location in the input directory:
+// `a/b.jpg` is written to `<output_dir>/a/b.jpg/`. The folder keeps the extension, so that
+// `a/b.png` next to it gets a folder of its own.
+#[must_use]
+pub fn output_folder_for(output_dir: &Path, image: &Path) -> PathBuf {
+    output_dir.join(image)
+}
+
+// Returns true if a previous run already saved its final image to the folder.
+#[must_use]
+pub fn is_finished(output_folder: &Path, output: &OutputOptions) -> bool {
+    output
+        .final_paths(output_folder)
+        .iter()
+        .any(|path| path.is_file())
+}
+
+// Evolves every image found in input_dir with the same options. on_result is called as soon as
+// each image is done, possibly from several threads at once. Results are returned in the order
+// the images were found.
+pub fn run_batch(
+    input_dir: &Path,
+    output_dir: &Path,
+    options: &EvolveOptions,
+    batch_options: &BatchOptions,
+    on_result: impl Fn(&BatchResult) + Sync,
+) -> io::Result<Vec<BatchResult>> {
+    let images = find_images(input_dir, &batch_options.pattern, Some(output_dir))?;
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new(Vec::with_capacity(images.len()));
+
+    thread::scope(|scope| {
+        for _ in 0..batch_options.jobs.max(1) {
+            scope.spawn(|| loop {
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                let Some(image) = images.get(index) else {
+                    break;
+                };
+
+                let output_folder = output_folder_for(output_dir, image);
+                let outcome = if !batch_options.overwrite && is_finished(&output_folder, &options.output) {
+                    Outcome::Skipped
+                } else {
+                    evolve_one(&input_dir.join(image), &output_folder, options)
+                };
+
+                let result = BatchResult {
+                    image: image.clone(),
+                    output_folder,
+                    outcome,
+                };
+                on_result(&result);
+                results.lock().unwrap().push((index, result));
+            });
+        }
+    });
+
+    let mut results = results.into_inner().unwrap();
+    results.sort_by_key(|&(index, _)| index);
+    Ok(results.into_iter().map(|(_, result)| result).collect())
+}
+
+fn evolve_one(input_path: &Path, output_folder: &Path, options: &EvolveOptions) -> Outcome {
+    if let Err(e) = fs::create_dir_all(output_folder) {
+        return Outcome::Failed(e.to_string());
+    }
+
+    // Keep the statistics of the last epoch for the report.
+    let mut outcome = Outcome::Finished {
+        num_shapes: 0,
+        score_per_pixel: 0.0,
+        elapsed: Duration::ZERO,
+    };
+    let start = Instant::now();
+    let mut observer = |event: &Event| {
+        if let Event::EpochFinished {
+            num_shapes,
+            score_per_pixel,
+            ..
+        } = *event
+        {
+            outcome = Outcome::Finished {
+                num_shapes,
+                score_per_pixel,
+                elapsed: start.elapsed(),
+            };
+        }
+    };
+
+    // A panic while evolving one image fails only that image, not the rest of the batch.
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        evolve(input_path, output_folder, options, &mut observer)
+    }));
+    match result {
+        Ok(Ok(())) => outcome,
+        Ok(Err(e)) => Outcome::Failed(e.to_string()),
+        Err(payload) => {
+            let message = payload
+                .downcast_ref::<&str>()
+                .map(|s| (*s).to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "unknown error".to_string());
+            Outcome::Failed(format!("panicked: {}", message))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::batch::{find_images, is_finished, output_folder_for, run_batch};
+    use crate::batch::{BatchOptions, Outcome};
+    use crate::evolve::EvolveOptions;
+    use crate::output::OutputOptions;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("shape-evolution-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_find_images() {
+        let dir = temp_dir("find-images");
+        fs::create_dir_all(dir.join("a/b")).unwrap();
+        for file in ["one.jpg", "notes.txt", "a/two.png", "a/b/three.jpg"] {
+            fs::write(dir.join(file), "").unwrap();
+        }
+
+        let all = find_images(&dir, &glob::Pattern::new("*").unwrap(), None).unwrap();
+        assert_eq!(
+            all,
+            [
+                PathBuf::from("a/b/three.jpg"),
+                PathBuf::from("a/two.png"),
+                PathBuf::from("one.jpg"),
+            ]
+        );
+
+        let jpegs = find_images(&dir, &glob::Pattern::new("*.jpg").unwrap(), None).unwrap();
+        assert_eq!(
+            jpegs,
+            [PathBuf::from("a/b/three.jpg"), PathBuf::from("one.jpg")]
+        );
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_find_images_skips_directory_links() {
+        let dir = temp_dir("directory-links");
+        fs::create_dir_all(dir.join("a")).unwrap();
+        fs::write(dir.join("a/one.png"), "").unwrap();
+        std::os::unix::fs::symlink(&dir, dir.join("a/loop")).unwrap();
+
+        let all = find_images(&dir, &glob::Pattern::new("*").unwrap(), None).unwrap();
+        assert_eq!(all, [PathBuf::from("a/one.png")]);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_find_images_skips_output_dir() {
+        let dir = temp_dir("skip-output");
+        fs::create_dir_all(dir.join("out/one.jpg")).unwrap();
+        fs::write(dir.join("one.jpg"), "").unwrap();
+        fs::write(dir.join("out/one.jpg/out.png"), "").unwrap();
+
+        let pattern = glob::Pattern::new("*").unwrap();
+        let all = find_images(&dir, &pattern, Some(&dir.join("out"))).unwrap();
+        assert_eq!(all, [PathBuf::from("one.jpg")]);
+        // The output directory does not have to exist yet.
+        let all = find_images(&dir, &pattern, Some(&dir.join("missing"))).unwrap();
+        assert_eq!(all.len(), 2);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_output_folder_mirrors_input() {
+        assert_eq!(
+            output_folder_for(Path::new("out"), Path::new("a/b/three.jpg")),
+            PathBuf::from("out/a/b/three.jpg")
+        );
+        // Images which only differ in their extension get separate folders.
+        assert_ne!(
+            output_folder_for(Path::new("out"), Path::new("a/b.jpg")),
+            output_folder_for(Path::new("out"), Path::new("a/b.png"))
+        );
+    }
+
+    #[test]
+    fn test_run_batch_keeps_images_with_same_stem_apart() {
+        let dir = temp_dir("same-stem");
+        let input_dir = dir.join("in");
+        fs::create_dir_all(&input_dir).unwrap();
+        let img = image::RgbaImage::from_pixel(8, 8, image::Rgba([200, 30, 30, 255]));
+        img.save(input_dir.join("b.png")).unwrap();
+        image::DynamicImage::ImageRgba8(img).to_rgb8().save(input_dir.join("b.jpg")).unwrap();
+
+        let options = EvolveOptions {
+            num_epochs: 2,
+            num_gens: 2,
+            generation_size: 30,
+            seed: Some(1),
+            ..EvolveOptions::default()
+        };
+        let batch_options = BatchOptions {
+            pattern: glob::Pattern::new("*").unwrap(),
+            jobs: 2,
+            overwrite: false,
+        };
+        let output_dir = input_dir.join("out");
+        let run = || run_batch(&input_dir, &output_dir, &options, &batch_options, |_| {}).unwrap();
+
+        let results = run();
+        assert_eq!(results.len(), 2);
+        assert_ne!(results[0].output_folder, results[1].output_folder);
+        for result in &results {
+            assert!(matches!(result.outcome, Outcome::Finished { .. }));
+            assert!(is_finished(&result.output_folder, &options.output));
+        }
+
+        // The output directory is inside the input directory, but its images are not processed.
+        let results = run();
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(|result| matches!(result.outcome, Outcome::Skipped)));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_is_finished() {
+        let dir = temp_dir("is-finished");
+        let output = OutputOptions::default();
+        assert!(!is_finished(&dir, &output));
+        fs::write(dir.join("out-20-1234.jpg"), "").unwrap();
+        assert!(!is_finished(&dir, &output));
+        fs::write(dir.join("out.png"), "").unwrap();
+        assert!(is_finished(&dir, &output));
+
+        let renamed = OutputOptions {
+            final_name: "final".to_string(),
+            ..OutputOptions::default()
+        };
+        assert!(!is_finished(&dir, &renamed));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
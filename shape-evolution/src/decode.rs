@@ -0,0 +1,342 @@
+// Decoding of target images, shared by the command line tool and the browser. Images are
+// converted to RGBA, keeping any transparency, and rotated according to their EXIF orientation so
+// that phone photos come out upright. Oversized images can be rejected before they are decoded,
+// and JPEGs can be decoded directly at a reduced size.
+
+use image::codecs::jpeg::JpegDecoder;
+use image::error::{LimitError, LimitErrorKind};
+use image::io::{Limits, Reader};
+use image::{DynamicImage, ImageDecoder, ImageError, ImageFormat, ImageResult, RgbaImage};
+use std::fmt;
+use std::fs;
+use std::io::Cursor;
+use std::path::Path;
+
+// Bounds on the size of decoded images. None means no bound, except for max_alloc.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DecodeOptions {
+    // Larger images are rejected before decoding, unless they are JPEGs that can be decoded at a
+    // reduced size within the limit.
+    pub max_pixels: Option<u64>,
+    // The most memory, in bytes, the decoder may allocate. Passed on to image::io::Limits, whose
+    // default of 512 MiB applies when this is not set.
+    pub max_alloc: Option<u64>,
+    // JPEGs are decoded at the smallest size that keeps at least this many pixels, down to 1/8 of
+    // their width and height. This is much cheaper than decoding in full and resizing afterwards.
+    pub min_pixels: Option<u64>,
+}
+
+#[derive(Debug)]
+pub enum DecodeError {
+    TooLarge { width: u32, height: u32, max_pixels: u64 },
+    Image(ImageError),
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TooLarge {
+                width,
+                height,
+                max_pixels,
+            } => write!(
+                f,
+                "image is {} × {} pixels, more than the limit of {} pixels",
+                width, height, max_pixels
+            ),
+            Self::Image(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+impl From<ImageError> for DecodeError {
+    fn from(e: ImageError) -> Self {
+        Self::Image(e)
+    }
+}
+
+impl From<DecodeError> for ImageError {
+    fn from(e: DecodeError) -> Self {
+        match e {
+            DecodeError::TooLarge { .. } => {
+                Self::Limits(LimitError::from_kind(LimitErrorKind::DimensionError))
+            }
+            DecodeError::Image(e) => e,
+        }
+    }
+}
+
+// Decodes an encoded image in any supported format.
+pub fn decode_image(bytes: &[u8]) -> ImageResult<RgbaImage> {
+    Ok(decode_image_with_options(bytes, &DecodeOptions::default())?)
+}
+
+pub fn decode_image_with_options(
+    bytes: &[u8],
+    options: &DecodeOptions,
+) -> Result<RgbaImage, DecodeError> {
+    let mut limits = Limits::default();
+    if let Some(max_alloc) = options.max_alloc {
+        limits.max_alloc = Some(max_alloc);
+    }
+
+    let format = image::guess_format(bytes)?;
+    let img = if format == ImageFormat::Jpeg {
+        decode_jpeg(bytes, options, limits)?
+    } else {
+        let (width, height) = Reader::with_format(Cursor::new(bytes), format).into_dimensions()?;
+        check_size(width, height, options)?;
+        let mut reader = Reader::with_format(Cursor::new(bytes), format);
+        reader.limits(limits);
+        reader.decode()?
+    };
+
+    let img = match read_exif_orientation(bytes) {
+        Some(orientation) => correct_orientation(img, orientation),
+        None => img,
+    };
+    Ok(img.into_rgba8())
+}
+
+fn decode_jpeg(
+    bytes: &[u8],
+    options: &DecodeOptions,
+    mut limits: Limits,
+) -> Result<DynamicImage, DecodeError> {
+    let mut decoder = JpegDecoder::new(Cursor::new(bytes))?;
+    let (width, height) = decoder.dimensions();
+    let scale = jpeg_scale(width, height, options);
+    if scale > 1 {
+        let (scaled_width, scaled_height) = scaled_size(width, height, scale);
+        // The decoder picks the smallest scale that is at least as large as the requested size,
+        // which is exactly the scaled size. Both fit in u16, as JPEGs are at most 65535 pixels
+        // wide and high.
+        decoder.scale(scaled_width as u16, scaled_height as u16)?;
+    }
+
+    let (width, height) = decoder.dimensions();
+    check_size(width, height, options)?;
+    limits.reserve(decoder.total_bytes())?;
+    decoder.set_limits(limits)?;
+    Ok(DynamicImage::from_decoder(decoder)?)
+}
+
+// Returns the factor, 1, 2, 4 or 8, by which a JPEG is scaled down while decoding. Fitting within
+// max_pixels takes precedence over keeping min_pixels.
+fn jpeg_scale(width: u32, height: u32, options: &DecodeOptions) -> u32 {
+    let num_pixels = |scale| {
+        let (width, height) = scaled_size(width, height, scale);
+        u64::from(width) * u64::from(height)
+    };
+    let mut scale = 1;
+    if let Some(min_pixels) = options.min_pixels {
+        while scale < 8 && num_pixels(scale * 2) >= min_pixels {
+            scale *= 2;
+        }
+    }
+    if let Some(max_pixels) = options.max_pixels {
+        while scale < 8 && num_pixels(scale) > max_pixels {
+            scale *= 2;
+        }
+    }
+    scale
+}
+
+fn scaled_size(width: u32, height: u32, scale: u32) -> (u32, u32) {
+    (width.div_ceil(scale), height.div_ceil(scale))
+}
+
+fn check_size(width: u32, height: u32, options: &DecodeOptions) -> Result<(), DecodeError> {
+    match options.max_pixels {
+        Some(max_pixels) if u64::from(width) * u64::from(height) > max_pixels => {
+            Err(DecodeError::TooLarge {
+                width,
+                height,
+                max_pixels,
+            })
+        }
+        _ => Ok(()),
+    }
+}
+
+pub fn open_image(path: impl AsRef<Path>) -> ImageResult<RgbaImage> {
+    decode_image(&fs::read(path)?)
+}
+
+// Returns the EXIF orientation of the image, or None if it has no valid orientation tag. Images
+// without EXIF data are common, so this is not an error.
+fn read_exif_orientation(bytes: &[u8]) -> Option<u32> {
+    let exif_data = exif::Reader::new()
+        .read_from_container(&mut Cursor::new(bytes))
+        .ok()?;
+    let orientation = exif_data.get_field(exif::Tag::Orientation, exif::In::PRIMARY)?;
+    match orientation.value.get_uint(0) {
+        Some(v @ 1..=8) => Some(v),
+        _ => None,
+    }
+}
+
+fn correct_orientation(img: DynamicImage, orientation: u32) -> DynamicImage {
+    match orientation {
+        1 => img,
+        2 => img.fliph(),
+        3 => img.rotate180(),
+        4 => img.rotate180().fliph(),
+        5 => img.rotate90().fliph(),
+        6 => img.rotate90(),
+        7 => img.rotate270().fliph(),
+        8 => img.rotate270(),
+        _ => panic!("Invalid exif orientation value"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::decode::{
+        correct_orientation, decode_image, decode_image_with_options, DecodeError, DecodeOptions,
+    };
+    use image::{DynamicImage, ImageOutputFormat, Rgba, RgbaImage};
+    use std::io::Cursor;
+
+    fn testing_image() -> image::DynamicImage {
+        let mut img: image::GrayImage = image::ImageBuffer::new(2, 2);
+        img.put_pixel(0, 0, [0].into());
+        img.put_pixel(1, 0, [1].into());
+        img.put_pixel(0, 1, [2].into());
+        img.put_pixel(1, 1, [3].into());
+        image::DynamicImage::ImageLuma8(img)
+    }
+
+    fn assert_image(img: image::DynamicImage, vals: [u8; 4]) {
+        let img = img.as_luma8().expect("was not provided a luma8 image");
+        assert_eq!(img.width(), 2);
+        assert_eq!(img.height(), 2);
+        let pixels: Vec<u8> = img.pixels().map(|luma| luma.0[0]).collect();
+        let vals = vals.to_vec();
+        assert_eq!(pixels, vals);
+    }
+
+    fn test_orientation(n: u32, expected: [u8; 4]) {
+        let img = correct_orientation(testing_image(), n);
+        assert_image(img, expected);
+    }
+
+    #[test]
+    fn test_orientation_1() {
+        test_orientation(1, [0, 1, 2, 3]);
+    }
+    #[test]
+    fn test_orientation_2() {
+        test_orientation(2, [1, 0, 3, 2]);
+    }
+    #[test]
+    fn test_orientation_3() {
+        test_orientation(3, [3, 2, 1, 0]);
+    }
+    #[test]
+    fn test_orientation_4() {
+        test_orientation(4, [2, 3, 0, 1]);
+    }
+    #[test]
+    fn test_orientation_5() {
+        test_orientation(5, [0, 2, 1, 3]);
+    }
+    #[test]
+    fn test_orientation_6() {
+        test_orientation(6, [2, 0, 3, 1]);
+    }
+    #[test]
+    fn test_orientation_7() {
+        test_orientation(7, [3, 1, 2, 0]);
+    }
+    #[test]
+    fn test_orientation_8() {
+        test_orientation(8, [1, 3, 0, 2]);
+    }
+
+    fn encode(img: RgbaImage, format: ImageOutputFormat) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        let img = match format {
+            ImageOutputFormat::Jpeg(_) => {
+                DynamicImage::ImageRgb8(DynamicImage::ImageRgba8(img).into_rgb8())
+            }
+            _ => DynamicImage::ImageRgba8(img),
+        };
+        img.write_to(&mut Cursor::new(&mut bytes), format).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn test_decode_keeps_alpha() {
+        let img = RgbaImage::from_fn(3, 2, |x, _y| Rgba([10, 20, 30, (x * 100) as u8]));
+        let decoded = decode_image(&encode(img.clone(), ImageOutputFormat::Png)).unwrap();
+        assert_eq!(decoded, img);
+    }
+
+    #[test]
+    fn test_decode_applies_exif_orientation() {
+        let img = RgbaImage::from_fn(16, 8, |_x, _y| Rgba([200, 200, 200, 255]));
+        let jpeg = encode(img, ImageOutputFormat::Jpeg(90));
+        assert_eq!(decode_image(&jpeg).unwrap().dimensions(), (16, 8));
+
+        // An APP1 segment holding a big-endian TIFF header and a single IFD entry: orientation 6,
+        // which rotates the image by 90 degrees.
+        let mut exif = b"Exif\0\0MM\0\x2a\0\0\0\x08\0\x01".to_vec();
+        exif.extend_from_slice(b"\x01\x12\0\x03\0\0\0\x01\0\x06\0\0\0\0\0\0");
+        let mut rotated = jpeg[..2].to_vec();
+        rotated.extend_from_slice(&[0xff, 0xe1]);
+        rotated.extend_from_slice(&u16::try_from(exif.len() + 2).unwrap().to_be_bytes());
+        rotated.extend_from_slice(&exif);
+        rotated.extend_from_slice(&jpeg[2..]);
+        assert_eq!(decode_image(&rotated).unwrap().dimensions(), (8, 16));
+    }
+
+    #[test]
+    fn test_decode_rejects_large_images() {
+        let png = encode(RgbaImage::new(40, 30), ImageOutputFormat::Png);
+        let options = DecodeOptions {
+            max_pixels: Some(1000),
+            ..DecodeOptions::default()
+        };
+        match decode_image_with_options(&png, &options) {
+            Err(DecodeError::TooLarge { width, height, .. }) => {
+                assert_eq!((width, height), (40, 30));
+            }
+            result => panic!("expected the image to be too large, got {:?}", result),
+        }
+
+        let options = DecodeOptions {
+            max_alloc: Some(1000),
+            ..DecodeOptions::default()
+        };
+        assert!(matches!(
+            decode_image_with_options(&png, &options),
+            Err(DecodeError::Image(image::ImageError::Limits(_)))
+        ));
+        assert!(decode_image(&png).is_ok());
+    }
+
+    #[test]
+    fn test_decode_scales_jpeg() {
+        let jpeg = encode(RgbaImage::new(80, 61), ImageOutputFormat::Jpeg(90));
+        let decode = |max_pixels, min_pixels| {
+            let options = DecodeOptions {
+                max_pixels,
+                min_pixels,
+                ..DecodeOptions::default()
+            };
+            decode_image_with_options(&jpeg, &options).map(|img| img.dimensions())
+        };
+
+        assert_eq!(decode(None, None).unwrap(), (80, 61));
+        assert_eq!(decode(None, Some(1000)).unwrap(), (40, 31));
+        assert_eq!(decode(None, Some(300)).unwrap(), (20, 16));
+        assert_eq!(decode(None, Some(1)).unwrap(), (10, 8));
+        // Too large images are scaled down to fit, even below the minimum size.
+        assert_eq!(decode(Some(2000), None).unwrap(), (40, 31));
+        assert_eq!(decode(Some(100), Some(1000)).unwrap(), (10, 8));
+        assert!(decode(Some(50), None).is_err());
+    }
+}
@@ -1,9 +1,154 @@
+use crate::decode::open_image;
+use crate::image_diff::image_diff;
 use crate::mutate::Mutate;
-use crate::random_shape::{RandomCircle, RandomShape};
+use crate::output::{apply_alpha_mask, file_name, save_image, OutputFormat, OutputOptions};
+use crate::palette::ColorMode;
+use crate::progress::{Event, Observer};
+use crate::random_shape::{RandomCircle, RandomShape, ShapeOptions, SizeSchedule};
+use crate::resize::ResizeFilter;
+use crate::score::Score;
 use image::RgbaImage;
-use rand;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+use std::fmt;
 use std::iter;
 use std::path::Path;
+use std::time::Instant;
+
+// Settings for a complete evolution run.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EvolveOptions {
+    pub num_epochs: u32,
+    pub num_gens: u32,
+    pub generation_size: usize,
+    pub shapes_per_epoch: usize,
+    // The target image is shrunk by this factor before evolving shapes against it.
+    pub scale_down: f64,
+    pub resize_filter: ResizeFilter,
+    // Seed for the random number generator. Runs with the same seed and settings produce the
+    // same image. A random seed is used if this is not set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+    pub shape: ShapeOptions,
+    pub output: OutputOptions,
+}
+
+impl Default for EvolveOptions {
+    fn default() -> Self {
+        Self {
+            num_epochs: 200,
+            num_gens: 50,
+            generation_size: 100,
+            shapes_per_epoch: 1,
+            scale_down: 1.0,
+            resize_filter: ResizeFilter::default(),
+            seed: None,
+            shape: ShapeOptions::default(),
+            output: OutputOptions::default(),
+        }
+    }
+}
+
+impl EvolveOptions {
+    // Checks the settings that would otherwise make a run silently produce nothing, such as
+    // committing no shapes per epoch.
+    pub fn validate(&self) -> Result<(), OptionsError> {
+        let at_least_one = [
+            (Setting::NumGens, u64::from(self.num_gens)),
+            (Setting::GenerationSize, self.generation_size as u64),
+            (Setting::ShapesPerEpoch, self.shapes_per_epoch as u64),
+        ];
+        for (setting, value) in at_least_one {
+            if value == 0 {
+                return Err(OptionsError::Zero(setting));
+            }
+        }
+        // Also rejects NaN. Infinity would shrink the target to nothing.
+        if !(self.scale_down > 0.0 && self.scale_down.is_finite()) {
+            return Err(OptionsError::NotPositive(Setting::ScaleDown));
+        }
+
+        let shape = &self.shape;
+        if shape.color_mode == ColorMode::Extracted(0) {
+            return Err(OptionsError::Zero(Setting::PaletteSize));
+        }
+        if let Some(max_radius) = shape.max_radius {
+            if max_radius < shape.min_radius {
+                return Err(OptionsError::RadiusRange {
+                    min_radius: shape.min_radius,
+                    max_radius,
+                });
+            }
+        }
+        match shape.size_schedule {
+            SizeSchedule::Linear { num_shapes: 0, .. }
+            | SizeSchedule::Exponential { half_life: 0, .. } => {
+                Err(OptionsError::Zero(Setting::ScheduleLength))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+// The settings of EvolveOptions that validate checks on their own.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Setting {
+    NumGens,
+    GenerationSize,
+    ShapesPerEpoch,
+    ScaleDown,
+    PaletteSize,
+    // The length of a linear size schedule, or the half-life of an exponential one.
+    ScheduleLength,
+}
+
+impl Setting {
+    // The name of the setting in config files, where it has one.
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::NumGens => "num_gens",
+            Self::GenerationSize => "generation_size",
+            Self::ShapesPerEpoch => "shapes_per_epoch",
+            Self::ScaleDown => "scale_down",
+            Self::PaletteSize => "palette_size",
+            Self::ScheduleLength => "schedule_length",
+        }
+    }
+}
+
+// A setting of EvolveOptions that is out of range.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum OptionsError {
+    // The setting is 0 but must be at least 1.
+    Zero(Setting),
+    // The setting must be a finite number greater than 0.
+    NotPositive(Setting),
+    RadiusRange { min_radius: u32, max_radius: u32 },
+}
+
+impl fmt::Display for OptionsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Zero(setting) => write!(f, "{} must be at least 1", setting.name()),
+            Self::NotPositive(setting) => {
+                write!(f, "{} must be a number greater than 0", setting.name())
+            }
+            Self::RadiusRange {
+                min_radius,
+                max_radius,
+            } => write!(
+                f,
+                "max_radius ({}) is smaller than min_radius ({})",
+                max_radius, min_radius
+            ),
+        }
+    }
+}
+
+impl std::error::Error for OptionsError {}
 
 // Sort shapes by how close to the target the current image becomes after drawing the
 // shape on top.
@@ -20,44 +165,54 @@ pub fn sort_generation(
 // Takes in the target image along with the current generation of shapes.
 // Returns the next generation of shapes.
 #[must_use]
-pub fn next_generation(
+pub fn next_generation<R: Rng + ?Sized>(
     target_img: &image::RgbaImage,
     current_img: &image::RgbaImage,
     current_gen: &[RandomCircle],
     mutation_factor: f64,
+    shape_options: &ShapeOptions,
+    rng: &mut R,
 ) -> Vec<RandomCircle> {
-    let rng = rand::thread_rng();
     let (imgx, imgy) = target_img.dimensions();
     let mut newvec = current_gen.to_vec();
 
     newvec = sort_generation(target_img, current_img, newvec);
     // Kill worst 80 shapes and replace them with mutated children of the survivors.
     newvec.truncate(20);
-    let children: Vec<RandomCircle> = newvec
-        .iter()
-        .flat_map(|shape| {
-            iter::repeat_with(|| shape.mutate(&mut rng.clone(), mutation_factor)).take(3)
-        })
-        .collect();
+    let mut children: Vec<RandomCircle> = Vec::with_capacity(newvec.len() * 3);
+    for shape in &newvec {
+        for _ in 0..3 {
+            children.push(shape.mutate(rng, mutation_factor, shape_options));
+        }
+    }
     newvec.extend(children);
-    newvec.extend(iter::repeat_with(|| RandomCircle::new(imgx, imgy)).take(20));
+    newvec.extend(
+        iter::repeat_with(|| RandomCircle::new_with_rng(imgx, imgy, shape_options, rng)).take(20),
+    );
     newvec
 }
 
-// Perform a single epoch, returning the best resulting shape and its corresponding score.
-// If no shape could be found which lowers the score, this function returns None.
-pub fn epoch(
+// Perform a single epoch, returning up to `shapes_per_epoch` shapes along with the score after
+// drawing all of them. The shapes are taken from the best of the final generation, skipping any
+// shape which overlaps one that was already chosen, so that each shape's score is unaffected by
+// the others. If no shape could be found which lowers the score, this function returns None.
+#[allow(clippy::too_many_arguments)]
+pub fn epoch<R: Rng + ?Sized>(
     generation_size: usize,
     num_gens: u32,
+    shapes_per_epoch: usize,
     target_img: &image::RgbaImage,
     current_img: &image::RgbaImage,
-    current_score: u128,
-) -> Option<(RandomCircle, u128)> {
+    current_score: Score,
+    shape_options: &ShapeOptions,
+    rng: &mut R,
+) -> Option<(Vec<RandomCircle>, Score)> {
     let (imgx, imgy) = target_img.dimensions();
 
-    let mut shapes: Vec<RandomCircle> = iter::repeat_with(|| RandomCircle::new(imgx, imgy))
-        .take(generation_size)
-        .collect();
+    let mut shapes: Vec<RandomCircle> =
+        iter::repeat_with(|| RandomCircle::new_with_rng(imgx, imgy, shape_options, rng))
+            .take(generation_size)
+            .collect();
 
     for i in 0..num_gens {
         let mutation_factor: f64 = 1.0 - 0.9 / f64::from(i * num_gens + 1);
@@ -66,77 +221,331 @@ pub fn epoch(
             current_img,
             &shapes,
             mutation_factor,
+            shape_options,
+            rng,
         );
     }
 
-    let best_shape = shapes
+    // Only shapes which lower the score are worth keeping, best first.
+    let mut candidates: Vec<(RandomCircle, i64)> = shapes
         .into_iter()
-        .min_by_key(|shape| shape.score(target_img, current_img))
-        .unwrap();
-
-    // Calculate the score for the current image at full scale.
-    let delta = best_shape.score(target_img, current_img);
-    let new_score = if delta >= 0 {
-        current_score + delta as u128
-    } else {
-        current_score - delta.unsigned_abs() as u128
-    };
+        .map(|shape| {
+            let delta = shape.score(target_img, current_img);
+            (shape, delta)
+        })
+        .filter(|&(_, delta)| delta < 0)
+        .collect();
+    candidates.sort_by_key(|&(_, delta)| delta);
 
-    println!("score per pixel: {}", new_score as f64 / (imgx * imgy) as f64);
+    let mut best_shapes: Vec<RandomCircle> = Vec::new();
+    let mut new_score = current_score;
+    for (shape, delta) in candidates {
+        if best_shapes.len() >= shapes_per_epoch {
+            break;
+        }
+        // Shapes which lower the score always have bounds inside the image.
+        let bounds = shape.get_bounds().unwrap();
+        let overlaps = best_shapes
+            .iter()
+            .any(|other| other.get_bounds().unwrap().intersects(&bounds));
+        if !overlaps {
+            new_score = new_score.apply(delta);
+            best_shapes.push(shape);
+        }
+    }
 
-    // Save the shape if it was an improvement
-    if new_score < current_score {
-        Some((best_shape, new_score))
-    } else {
+    if best_shapes.is_empty() {
         None
+    } else {
+        Some((best_shapes, new_score))
     }
 }
 
-pub fn evolve(input_path: &str, num_epochs: u32, num_gens: u32, output_folder: &str, scale_down: f64) {
-    let target_img = image::open(input_path).unwrap().to_rgba8();
-    let (width, height) = target_img.dimensions();
-    let mut output_img = RgbaImage::new(width, height);
+// Evolves shapes approximating the image at input_path, saving the result to output_folder.
+// Progress is reported to the observer as the run goes on.
+pub fn evolve(
+    input_path: impl AsRef<Path>,
+    output_folder: impl AsRef<Path>,
+    options: &EvolveOptions,
+    observer: &mut dyn Observer,
+) -> image::ImageResult<()> {
+    let output_folder = output_folder.as_ref();
+    let output = &options.output;
+    let full_target_img = open_image(input_path)?;
+    let (width, height) = full_target_img.dimensions();
 
-    let target_img = image::imageops::resize(
-        &target_img,
-        (f64::from(width) / scale_down) as u32,
-        (f64::from(height) / scale_down) as u32,
-        image::imageops::FilterType::Nearest,
+    let target_img = options.resize_filter.resize(
+        &full_target_img,
+        (f64::from(width) / options.scale_down) as u32,
+        (f64::from(height) / options.scale_down) as u32,
     );
     let (width, height) = target_img.dimensions();
 
+    let (output_scale, render_size) =
+        output.render_size((width, height), full_target_img.dimensions());
+    let (saved_width, saved_height) = output.saved_size(render_size);
+    let mut output_img = RgbaImage::new(render_size.0, render_size.1);
+
+    // Only formats with an alpha channel can keep the transparency of the target.
+    let has_transparency = full_target_img.pixels().any(|p| p[3] < 255);
+    let format = output.format.resolve(has_transparency);
+    let alpha_mask = (has_transparency && format == OutputFormat::Png).then(|| {
+        image::imageops::resize(
+            &full_target_img,
+            saved_width,
+            saved_height,
+            image::imageops::FilterType::Nearest,
+        )
+    });
+    let save = |img: &RgbaImage, name: String| {
+        let mut img = if (saved_width, saved_height) == img.dimensions() {
+            img.clone()
+        } else {
+            image::imageops::resize(
+                img,
+                saved_width,
+                saved_height,
+                image::imageops::FilterType::Lanczos3,
+            )
+        };
+        if let Some(mask) = &alpha_mask {
+            img = apply_alpha_mask(&img, mask);
+        }
+        save_image(&img, &output_folder.join(name), format, output.jpeg_quality)
+    };
+
+    let shape_options = ShapeOptions {
+        color_mode: options.shape.color_mode.clone().resolve(&target_img),
+        ..options.shape.clone()
+    };
+    let mut num_shapes = 0;
+    let mut rng = match options.seed {
+        Some(seed) => ChaCha8Rng::seed_from_u64(seed),
+        None => ChaCha8Rng::from_entropy(),
+    };
+
     let mut current_img = RgbaImage::new(width, height);
-    let mut score = u128::from(width * height) * 255 * 3;
+    let mut score = image_diff(&target_img, &current_img);
 
-    for i in 1..=num_epochs {
-        match epoch(
-            100,
-            num_gens,
+    let start = Instant::now();
+    for i in 1..=options.num_epochs {
+        observer.on_event(&Event::EpochStarted {
+            epoch: i,
+            num_epochs: options.num_epochs,
+        });
+
+        let accepted = match epoch(
+            options.generation_size,
+            options.num_gens,
+            options.shapes_per_epoch,
             &target_img,
             &current_img,
             score,
+            &shape_options.for_shape_count(width, height, num_shapes),
+            &mut rng,
         ) {
-            Some((best_shape, new_score)) => {
+            Some((best_shapes, new_score)) => {
                 score = new_score;
-                current_img = best_shape.draw(&current_img);
-                output_img = best_shape.scale_up(scale_down).draw(&output_img);
+                for best_shape in best_shapes {
+                    num_shapes += 1;
+                    current_img = best_shape.draw(&current_img);
+                    best_shape.render(&mut output_img, output_scale);
+                    observer.on_event(&Event::ShapeAccepted {
+                        epoch: i,
+                        shape: &best_shape,
+                    });
+                }
+                true
             }
             None => {
-                //println!("Discarded epoch");
+                observer.on_event(&Event::EpochRejected { epoch: i });
+                false
             }
+        };
+
+        if output.snapshots.is_due(i, accepted) {
+            save(
+                &output_img,
+                file_name(&output.snapshot_name, format, i, score, num_shapes),
+            )?;
         }
 
-        // Save the output buffer periodically.
-        if i % 20 == 0 {
-            output_img
-                .save(Path::new(output_folder).join(format!("out-{}-{}.jpg", i, score)))
-                .expect("Could not save image");
+        observer.on_event(&Event::EpochFinished {
+            epoch: i,
+            num_epochs: options.num_epochs,
+            num_gens: options.num_gens,
+            num_shapes,
+            score,
+            score_per_pixel: score.per_pixel(width * height),
+            elapsed: start.elapsed(),
+        });
+    }
+
+    save(&output_img, format!("{}.{}", output.final_name, format.extension()))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::evolve::{epoch, EvolveOptions, OptionsError, Setting};
+    use crate::image_diff::image_diff;
+    use crate::output::{OutputFormat, OutputOptions, Snapshots};
+    use crate::palette::{ColorMode, Palette};
+    use crate::random_shape::{RandomShape, ShapeOptions, SizeSchedule};
+    use crate::resize::ResizeFilter;
+    use image::RgbaImage;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    #[test]
+    fn test_partial_options_use_defaults() {
+        let options: EvolveOptions = toml::from_str(
+            "num_epochs = 10\n[shape]\ncolor_mode = { extracted = 8 }\nmax_radius = 20\n",
+        )
+        .unwrap();
+        assert_eq!(
+            options,
+            EvolveOptions {
+                num_epochs: 10,
+                shape: ShapeOptions {
+                    color_mode: ColorMode::Extracted(8),
+                    max_radius: Some(20),
+                    ..ShapeOptions::default()
+                },
+                ..EvolveOptions::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_options_round_trip() {
+        let options = EvolveOptions {
+            scale_down: 2.5,
+            resize_filter: ResizeFilter::Lanczos3,
+            shape: ShapeOptions {
+                color_mode: ColorMode::Palette(Palette::parse("#ff0000\n#0000ff").unwrap()),
+                size_schedule: SizeSchedule::Linear {
+                    final_max_radius: 4,
+                    num_shapes: 300,
+                },
+                ..ShapeOptions::default()
+            },
+            output: OutputOptions {
+                format: OutputFormat::Png,
+                snapshots: Snapshots::Every(5),
+                width: Some(1920),
+                ..OutputOptions::default()
+            },
+            ..EvolveOptions::default()
+        };
+
+        let text = toml::to_string(&options).unwrap();
+        assert_eq!(toml::from_str::<EvolveOptions>(&text).unwrap(), options);
+
+        let text = serde_json::to_string(&options).unwrap();
+        assert_eq!(serde_json::from_str::<EvolveOptions>(&text).unwrap(), options);
+    }
+
+    #[test]
+    fn test_validate() {
+        assert_eq!(EvolveOptions::default().validate(), Ok(()));
+
+        let options = EvolveOptions {
+            shapes_per_epoch: 0,
+            ..EvolveOptions::default()
+        };
+        assert_eq!(options.validate(), Err(OptionsError::Zero(Setting::ShapesPerEpoch)));
+        assert_eq!(
+            options.validate().unwrap_err().to_string(),
+            "shapes_per_epoch must be at least 1"
+        );
+
+        for scale_down in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+            let options = EvolveOptions {
+                scale_down,
+                ..EvolveOptions::default()
+            };
+            assert_eq!(options.validate(), Err(OptionsError::NotPositive(Setting::ScaleDown)));
         }
 
-        println!("Done epoch {} of {}", i, num_epochs);
+        let mut options = EvolveOptions::default();
+        options.shape.color_mode = ColorMode::Extracted(0);
+        assert_eq!(options.validate(), Err(OptionsError::Zero(Setting::PaletteSize)));
+
+        let mut options = EvolveOptions::default();
+        options.shape.min_radius = 5;
+        options.shape.max_radius = Some(4);
+        assert_eq!(
+            options.validate(),
+            Err(OptionsError::RadiusRange {
+                min_radius: 5,
+                max_radius: 4
+            })
+        );
+
+        let mut options = EvolveOptions::default();
+        options.shape.size_schedule = SizeSchedule::Linear {
+            final_max_radius: 2,
+            num_shapes: 0,
+        };
+        assert_eq!(options.validate(), Err(OptionsError::Zero(Setting::ScheduleLength)));
     }
 
-    output_img
-        .save(Path::new(output_folder).join("out.jpg"))
-        .expect("Could not save image");
+    #[test]
+    fn test_epoch_commits_non_overlapping_shapes() {
+        let (imgx, imgy) = (60, 40);
+        let target_img = RgbaImage::from_fn(imgx, imgy, |x, y| {
+            image::Rgba([(x * 4) as u8, (y * 6) as u8, 200, 255])
+        });
+        let current_img = RgbaImage::from_fn(imgx, imgy, |_x, _y| image::Rgba([0, 0, 0, 255]));
+        let current_score = image_diff(&target_img, &current_img);
+        let options = ShapeOptions {
+            max_radius: Some(8),
+            ..ShapeOptions::default()
+        };
+
+        let mut rng = ChaCha8Rng::seed_from_u64(1);
+        let (shapes, new_score) = epoch(
+            100,
+            5,
+            4,
+            &target_img,
+            &current_img,
+            current_score,
+            &options,
+            &mut rng,
+        )
+        .unwrap();
+
+        assert!(!shapes.is_empty() && shapes.len() <= 4);
+        assert!(new_score < current_score);
+        for (i, a) in shapes.iter().enumerate() {
+            for b in &shapes[i + 1..] {
+                assert!(!a.get_bounds().unwrap().intersects(&b.get_bounds().unwrap()));
+            }
+        }
+    }
+
+    #[test]
+    fn test_epoch_is_reproducible_from_seed() {
+        let target_img =
+            RgbaImage::from_fn(30, 20, |x, y| image::Rgba([(x * 8) as u8, (y * 12) as u8, 90, 255]));
+        let current_img = RgbaImage::from_fn(30, 20, |_x, _y| image::Rgba([0, 0, 0, 255]));
+        let current_score = image_diff(&target_img, &current_img);
+        let run = |seed| {
+            let mut rng = ChaCha8Rng::seed_from_u64(seed);
+            epoch(
+                50,
+                3,
+                2,
+                &target_img,
+                &current_img,
+                current_score,
+                &ShapeOptions::default(),
+                &mut rng,
+            )
+        };
+
+        assert_eq!(run(7), run(7));
+        assert_ne!(run(7), run(8));
+    }
 }
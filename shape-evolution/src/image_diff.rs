@@ -1,5 +1,11 @@
+use crate::score::Score;
+use image::{Rgba, RgbaImage};
+use serde::{Deserialize, Serialize};
+
+// Returns the total difference between two images. Each pixel's difference is weighted by the
+// alpha channel of the first image, so transparent areas of a target image do not count.
 #[must_use]
-pub fn image_diff(a: &image::RgbaImage, b: &image::RgbaImage) -> u128 {
+pub fn image_diff(a: &image::RgbaImage, b: &image::RgbaImage) -> Score {
     assert!(
         a.dimensions() == b.dimensions(),
         "Images have different sizes, {:?} != {:?}",
@@ -7,31 +13,156 @@ pub fn image_diff(a: &image::RgbaImage, b: &image::RgbaImage) -> u128 {
         b.dimensions()
     );
 
-    sum_chunked(a, b)
+    Score(sum_chunked(a, b))
+}
+
+// Returns the difference between two pixels, ignoring alpha and weighting the result by the
+// alpha of the first pixel. A fully opaque pixel keeps its unweighted difference.
+#[must_use]
+pub fn pixel_diff(p_a: &[u8], p_b: &[u8]) -> u32 {
+    let diff = u32::from(p_a[0].abs_diff(p_b[0]))
+        + u32::from(p_a[1].abs_diff(p_b[1]))
+        + u32::from(p_a[2].abs_diff(p_b[2]));
+    diff * u32::from(p_a[3]) / 255
+}
+
+// Returns an image showing how far each pixel of b is from a, from black for a perfect match
+// through red and yellow to white for the largest possible difference. The scale is fixed, so
+// heatmaps taken at different times can be compared.
+#[must_use]
+pub fn error_heatmap(a: &RgbaImage, b: &RgbaImage) -> RgbaImage {
+    assert_eq!(a.dimensions(), b.dimensions());
+    let (width, height) = a.dimensions();
+    let mut heatmap = RgbaImage::new(width, height);
+    for ((heat, p_a), p_b) in heatmap.pixels_mut().zip(a.pixels()).zip(b.pixels()) {
+        // Each third of the range brings in one more channel at full strength.
+        let level = pixel_diff(&p_a.0, &p_b.0);
+        let channel = |start: u32| u8::try_from(level.saturating_sub(start).min(255)).unwrap();
+        *heat = Rgba([channel(0), channel(255), channel(510), 255]);
+    }
+    heatmap
+}
+
+// The difference within a rectangular region of an image.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RegionDiff {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+    pub score: Score,
 }
 
-// Ignores alpha channel
-fn sum_chunked(samples_a: &[u8], samples_b: &[u8]) -> u128 {
+// Splits the images into a grid of `columns` by `rows` regions of nearly equal size and returns
+// the difference within each, row by row. The scores add up to image_diff. Both counts are
+// limited to the size of the image, so that no region is empty.
+#[must_use]
+pub fn region_diffs(a: &RgbaImage, b: &RgbaImage, columns: u32, rows: u32) -> Vec<RegionDiff> {
+    assert_eq!(a.dimensions(), b.dimensions());
+    let (width, height) = a.dimensions();
+    let columns = columns.clamp(1, width.max(1));
+    let rows = rows.clamp(1, height.max(1));
+    let edge = |i: u32, count: u32, size: u32| {
+        u32::try_from(u64::from(i) * u64::from(size) / u64::from(count)).unwrap()
+    };
+
+    let mut regions = Vec::new();
+    for row in 0..rows {
+        let (y, y_end) = (edge(row, rows, height), edge(row + 1, rows, height));
+        for column in 0..columns {
+            let (x, x_end) = (edge(column, columns, width), edge(column + 1, columns, width));
+            let mut score = 0;
+            for py in y..y_end {
+                for px in x..x_end {
+                    score += u64::from(pixel_diff(&a.get_pixel(px, py).0, &b.get_pixel(px, py).0));
+                }
+            }
+            regions.push(RegionDiff {
+                x,
+                y,
+                width: x_end - x,
+                height: y_end - y,
+                score: Score(score),
+            });
+        }
+    }
+    regions
+}
+
+fn sum_chunked(samples_a: &[u8], samples_b: &[u8]) -> u64 {
     samples_a
         .chunks_exact(4)
         .zip(samples_b.chunks_exact(4))
-        .fold(0, |sum, (p_a, p_b)| {
-            sum + (p_a[0].abs_diff(p_b[0])) as u128
-                + (p_a[1].abs_diff(p_b[1])) as u128
-                + (p_a[2].abs_diff(p_b[2])) as u128
-        })
+        .fold(0, |sum, (p_a, p_b)| sum + u64::from(pixel_diff(p_a, p_b)))
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::image_diff::image_diff;
-    use image::RgbaImage;
+    use crate::image_diff::{error_heatmap, image_diff, region_diffs};
+    use crate::score::Score;
+    use image::{Rgba, RgbaImage};
     #[test]
     fn test_diff_black_white() {
         let (imgx, imgy) = (10, 20);
         let black = RgbaImage::from_fn(imgx, imgy, |_x, _y| image::Rgba([0, 0, 0, 255]));
         let white = RgbaImage::from_fn(imgx, imgy, |_x, _y| image::Rgba([255, 255, 255, 255]));
 
-        assert_eq!(image_diff(&black, &white), (imgx * imgy * 255 * 3) as u128);
+        assert_eq!(image_diff(&black, &white), Score(u64::from(imgx * imgy * 255 * 3)));
+    }
+
+    #[test]
+    fn test_diff_ignores_transparent_target() {
+        let (imgx, imgy) = (10, 20);
+        let transparent = RgbaImage::from_fn(imgx, imgy, |_x, _y| image::Rgba([0, 0, 0, 0]));
+        let white = RgbaImage::from_fn(imgx, imgy, |_x, _y| image::Rgba([255, 255, 255, 255]));
+
+        assert_eq!(image_diff(&transparent, &white), Score(0));
+    }
+
+    #[test]
+    fn test_diff_weighted_by_target_alpha() {
+        let (imgx, imgy) = (10, 20);
+        let translucent = RgbaImage::from_fn(imgx, imgy, |_x, _y| image::Rgba([0, 0, 0, 51]));
+        let white = RgbaImage::from_fn(imgx, imgy, |_x, _y| image::Rgba([255, 255, 255, 255]));
+
+        assert_eq!(image_diff(&translucent, &white), Score(u64::from(imgx * imgy * 51 * 3)));
+    }
+
+    #[test]
+    fn test_error_heatmap() {
+        let target = RgbaImage::from_fn(4, 1, |_x, _y| image::Rgba([0, 0, 0, 255]));
+        let current = RgbaImage::from_fn(4, 1, |x, _y| match x {
+            0 => Rgba([0, 0, 0, 255]),
+            1 => Rgba([100, 0, 0, 255]),
+            2 => Rgba([255, 145, 0, 255]),
+            _ => Rgba([255, 255, 255, 255]),
+        });
+        let heatmap = error_heatmap(&target, &current);
+        assert_eq!(heatmap.get_pixel(0, 0), &Rgba([0, 0, 0, 255]));
+        assert_eq!(heatmap.get_pixel(1, 0), &Rgba([100, 0, 0, 255]));
+        assert_eq!(heatmap.get_pixel(2, 0), &Rgba([255, 145, 0, 255]));
+        assert_eq!(heatmap.get_pixel(3, 0), &Rgba([255, 255, 255, 255]));
+    }
+
+    #[test]
+    fn test_region_diffs() {
+        let (imgx, imgy) = (10, 7);
+        let target = RgbaImage::from_fn(imgx, imgy, |_x, _y| image::Rgba([0, 0, 0, 255]));
+        let current = RgbaImage::from_fn(imgx, imgy, |x, y| {
+            let value = if x < 5 && y < 3 { 255 } else { 1 };
+            image::Rgba([value, value, value, 255])
+        });
+
+        let regions = region_diffs(&target, &current, 3, 2);
+        assert_eq!(regions.len(), 6);
+        assert_eq!((regions[0].x, regions[0].y, regions[0].width, regions[0].height), (0, 0, 3, 3));
+        assert_eq!((regions[5].x, regions[5].y, regions[5].width, regions[5].height), (6, 3, 4, 4));
+        assert_eq!(regions[0].score, Score(3 * 3 * 255 * 3));
+        assert_eq!(regions[5].score, Score(4 * 4 * 3));
+        let total: u64 = regions.iter().map(|region| region.score.0).sum();
+        assert_eq!(Score(total), image_diff(&target, &current));
+
+        // There cannot be more regions than pixels.
+        assert_eq!(region_diffs(&target, &current, 100, 100).len(), 70);
     }
 }
@@ -1,4 +1,13 @@
+pub mod batch;
+pub mod decode;
 pub mod evolve;
 pub mod image_diff;
 pub mod mutate;
+pub mod output;
+pub mod palette;
+pub mod progress;
 pub mod random_shape;
+pub mod render;
+pub mod resize;
+pub mod score;
+pub mod span;
@@ -1,33 +1,456 @@
-use clap::Parser;
-use shape_evolution::evolve;
+use clap::{ArgEnum, Args, Parser, Subcommand};
+use shape_evolution::batch::{self, BatchOptions, Outcome};
+use shape_evolution::evolve::{self, EvolveOptions};
+use shape_evolution::output::{OutputFormat, Snapshots};
+use shape_evolution::palette::{ColorMode, Palette};
+use shape_evolution::progress::{
+    EpochLog, Event, JsonLines, LogFormat, Observer, ProgressBar, Quiet,
+};
+use shape_evolution::random_shape::SizeSchedule;
+use shape_evolution::resize::ResizeFilter;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+use std::time::Instant;
+use std::{fs, io, process, thread};
 
 #[derive(Parser, Debug)]
-#[clap()]
-struct Args {
+#[clap(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+struct Cli {
+    #[clap(subcommand)]
+    command: Option<Command>,
+
+    #[clap(flatten)]
+    run: RunArgs,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Evolve every image in a directory with the same settings
+    Batch(BatchArgs),
+}
+
+#[derive(Args, Debug)]
+struct RunArgs {
+    #[clap(short, long, required_unless_present = "dump-config")]
+    input_path: Option<String>,
+
+    #[clap(short, long, required_unless_present = "dump-config")]
+    output_folder: Option<String>,
+
+    /// How to report progress
+    #[clap(long, arg_enum, default_value = "bar")]
+    progress: ProgressKind,
+
+    /// Write one record per epoch to this file, as CSV if it ends in .csv and as JSON lines
+    /// otherwise
+    #[clap(long)]
+    log_file: Option<String>,
+
+    #[clap(flatten)]
+    evolution: EvolutionArgs,
+}
+
+#[derive(Args, Debug)]
+struct BatchArgs {
+    /// Directory to search for images, including its subdirectories
+    #[clap(short, long, required_unless_present = "dump-config")]
+    input_dir: Option<String>,
+
+    /// Directory to write results to, with one subfolder per image mirroring the input directory
+    #[clap(short, long, required_unless_present = "dump-config")]
+    output_dir: Option<String>,
+
+    /// Only process images whose path relative to the input directory matches this glob
+    #[clap(long, default_value = "*")]
+    pattern: glob::Pattern,
+
+    /// Number of images to process in parallel, defaults to the number of CPUs
     #[clap(short, long)]
-    input_path: String,
+    jobs: Option<usize>,
+
+    /// Process images again even if their output folder already holds a final image
+    #[clap(long)]
+    overwrite: bool,
+
+    #[clap(flatten)]
+    evolution: EvolutionArgs,
+}
+
+#[derive(Args, Debug)]
+struct EvolutionArgs {
+    /// Read settings from a TOML or JSON file. Flags given on the command line take precedence
+    #[clap(long)]
+    config: Option<String>,
 
+    /// Print the effective settings as TOML and exit
+    #[clap(long)]
+    dump_config: bool,
+
+    /// Number of epochs [default: 200]
     #[clap(short, long)]
-    output_folder: String,
+    epochs: Option<u32>,
 
-    #[clap(short, long, default_value_t = 200)]
-    epochs: u32,
+    /// Number of generations in each epoch [default: 50]
+    #[clap(short, long)]
+    gens: Option<u32>,
 
-    #[clap(short, long, default_value_t = 50)]
-    gens: u32,
+    /// Number of shapes in each generation [default: 100]
+    #[clap(long)]
+    population: Option<usize>,
 
+    /// Factor by which the input is scaled down before evolving [default: 1]
     #[clap(short, long)]
-    scale: f64,
+    scale: Option<f64>,
+
+    /// Filter used to scale the input down [default: area]
+    #[clap(long, arg_enum)]
+    resize_filter: Option<FilterKind>,
+
+    /// Largest number of non-overlapping shapes accepted in a single epoch [default: 1]
+    #[clap(long)]
+    shapes_per_epoch: Option<usize>,
+
+    /// Seed for the random number generator, to reproduce a previous run
+    #[clap(long)]
+    seed: Option<u64>,
+
+    /// Only draw shapes in shades of gray
+    #[clap(long, conflicts_with_all = &["palette", "palette-size"])]
+    grayscale: bool,
+
+    /// Only draw shapes with colors from a GIMP palette (.gpl) or hex list file
+    #[clap(long, conflicts_with = "palette-size")]
+    palette: Option<String>,
+
+    /// Only draw shapes with a palette of this many colors, extracted from the input image
+    #[clap(long)]
+    palette_size: Option<usize>,
+
+    /// Smallest radius of generated shapes, in pixels of the scaled-down image [default: 1]
+    #[clap(long)]
+    min_radius: Option<u32>,
+
+    /// Largest radius of generated shapes, in pixels of the scaled-down image
+    #[clap(long)]
+    max_radius: Option<u32>,
+
+    /// Shrink the largest radius as shapes are accepted
+    #[clap(long, arg_enum, requires_all = &["final-max-radius", "schedule-length"])]
+    size_schedule: Option<ScheduleKind>,
+
+    /// Largest radius at the end of the size schedule
+    #[clap(long)]
+    final_max_radius: Option<u32>,
+
+    /// Number of accepted shapes over which the linear schedule shrinks, or the half-life of
+    /// the exponential schedule
+    #[clap(long)]
+    schedule_length: Option<u32>,
+
+    /// Format of the saved images. "auto" saves PNG if the input has transparency and JPEG
+    /// otherwise [default: auto]
+    #[clap(long, arg_enum)]
+    format: Option<FormatKind>,
+
+    /// Quality of saved JPEG images, from 1 to 100 [default: 75]
+    #[clap(long, value_parser = clap::value_parser!(u8).range(1..=100))]
+    quality: Option<u8>,
+
+    /// When to save intermediate images: "never", "accepted" for every epoch which accepted a
+    /// shape, or a number of epochs [default: 20]
+    #[clap(long)]
+    snapshots: Option<Snapshots>,
+
+    /// File name of intermediate images without the extension. "{epoch}", "{score}" and
+    /// "{shapes}" are replaced by their current values [default: out-{epoch}-{score}]
+    #[clap(long)]
+    snapshot_name: Option<String>,
+
+    /// File name of the final image without the extension [default: out]
+    #[clap(long)]
+    output_name: Option<String>,
+
+    /// Width of the saved images in pixels. Keeps the aspect ratio unless a height is given too
+    #[clap(long)]
+    width: Option<u32>,
+
+    /// Height of the saved images in pixels. Keeps the aspect ratio unless a width is given too
+    #[clap(long)]
+    height: Option<u32>,
+}
+
+#[derive(ArgEnum, Clone, Copy, Debug)]
+enum ProgressKind {
+    /// A progress bar on stderr
+    Bar,
+    /// One JSON object per event on stdout
+    Json,
+    /// No progress output
+    Quiet,
+}
+
+#[derive(ArgEnum, Clone, Copy, Debug)]
+enum ScheduleKind {
+    Linear,
+    Exponential,
+}
+
+#[derive(ArgEnum, Clone, Copy, Debug)]
+enum FilterKind {
+    /// Average of the pixels covered
+    Area,
+    Nearest,
+    Triangle,
+    CatmullRom,
+    Gaussian,
+    Lanczos3,
+}
+
+#[derive(ArgEnum, Clone, Copy, Debug)]
+enum FormatKind {
+    Auto,
+    Png,
+    Jpeg,
 }
 
 fn main() {
-    let args = Args::parse();
-
-    evolve::evolve(
-        &args.input_path,
-        args.epochs,
-        args.gens,
-        &args.output_folder,
-        args.scale,
+    let cli = Cli::parse();
+
+    let evolution_args = match &cli.command {
+        Some(Command::Batch(args)) => &args.evolution,
+        None => &cli.run.evolution,
+    };
+    let options = evolve_options(evolution_args);
+
+    if evolution_args.dump_config {
+        let config = toml::to_string(&options).expect("Could not serialize settings");
+        print!("{}", config);
+        return;
+    }
+
+    match &cli.command {
+        Some(Command::Batch(args)) => run_batch(args, &options),
+        None => run(&cli.run, &options),
+    }
+}
+
+fn exit_with_error(message: String) -> ! {
+    eprintln!("Error: {}", message);
+    process::exit(1);
+}
+
+fn load_config(path: &str) -> EvolveOptions {
+    let text = fs::read_to_string(path)
+        .unwrap_or_else(|e| exit_with_error(format!("could not read {}: {}", path, e)));
+    let is_json = Path::new(path)
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+    let options = if is_json {
+        serde_json::from_str(&text).map_err(|e| e.to_string())
+    } else {
+        toml::from_str(&text).map_err(|e| e.to_string())
+    };
+    options.unwrap_or_else(|e| exit_with_error(format!("could not parse {}: {}", path, e)))
+}
+
+// Starts from the config file, if any, and overrides it with the flags given on the command line.
+fn evolve_options(args: &EvolutionArgs) -> EvolveOptions {
+    let mut options = match &args.config {
+        Some(path) => load_config(path),
+        None => EvolveOptions::default(),
+    };
+
+    if let Some(epochs) = args.epochs {
+        options.num_epochs = epochs;
+    }
+    if let Some(gens) = args.gens {
+        options.num_gens = gens;
+    }
+    if let Some(population) = args.population {
+        options.generation_size = population;
+    }
+    if let Some(scale) = args.scale {
+        options.scale_down = scale;
+    }
+    if let Some(filter) = args.resize_filter {
+        options.resize_filter = match filter {
+            FilterKind::Area => ResizeFilter::Area,
+            FilterKind::Nearest => ResizeFilter::Nearest,
+            FilterKind::Triangle => ResizeFilter::Triangle,
+            FilterKind::CatmullRom => ResizeFilter::CatmullRom,
+            FilterKind::Gaussian => ResizeFilter::Gaussian,
+            FilterKind::Lanczos3 => ResizeFilter::Lanczos3,
+        };
+    }
+    if let Some(shapes_per_epoch) = args.shapes_per_epoch {
+        options.shapes_per_epoch = shapes_per_epoch;
+    }
+    if let Some(seed) = args.seed {
+        options.seed = Some(seed);
+    }
+
+    if args.grayscale {
+        options.shape.color_mode = ColorMode::Grayscale;
+    } else if let Some(path) = &args.palette {
+        let text = fs::read_to_string(path)
+            .unwrap_or_else(|e| exit_with_error(format!("could not read {}: {}", path, e)));
+        let palette = Palette::parse(&text)
+            .unwrap_or_else(|e| exit_with_error(format!("could not parse {}: {}", path, e)));
+        options.shape.color_mode = ColorMode::Palette(palette);
+    } else if let Some(size) = args.palette_size {
+        options.shape.color_mode = ColorMode::Extracted(size);
+    }
+
+    if let Some(min_radius) = args.min_radius {
+        options.shape.min_radius = min_radius;
+    }
+    if let Some(max_radius) = args.max_radius {
+        options.shape.max_radius = Some(max_radius);
+    }
+
+    match (args.size_schedule, args.final_max_radius, args.schedule_length) {
+        (Some(ScheduleKind::Linear), Some(final_max_radius), Some(num_shapes)) => {
+            options.shape.size_schedule = SizeSchedule::Linear {
+                final_max_radius,
+                num_shapes,
+            };
+        }
+        (Some(ScheduleKind::Exponential), Some(final_max_radius), Some(half_life)) => {
+            options.shape.size_schedule = SizeSchedule::Exponential {
+                final_max_radius,
+                half_life,
+            };
+        }
+        _ => {}
+    }
+
+    if let Some(format) = args.format {
+        options.output.format = match format {
+            FormatKind::Auto => OutputFormat::Auto,
+            FormatKind::Png => OutputFormat::Png,
+            FormatKind::Jpeg => OutputFormat::Jpeg,
+        };
+    }
+    if let Some(quality) = args.quality {
+        options.output.jpeg_quality = quality;
+    }
+    if let Some(snapshots) = args.snapshots {
+        options.output.snapshots = snapshots;
+    }
+    if let Some(snapshot_name) = &args.snapshot_name {
+        options.output.snapshot_name = snapshot_name.clone();
+    }
+    if let Some(output_name) = &args.output_name {
+        options.output.final_name = output_name.clone();
+    }
+    if args.width.is_some() || args.height.is_some() {
+        options.output.width = args.width;
+        options.output.height = args.height;
+    }
+
+    if let Err(e) = options.validate() {
+        exit_with_error(format!("invalid settings: {}", e));
+    }
+    options
+}
+
+fn run(args: &RunArgs, options: &EvolveOptions) {
+    let mut progress: Box<dyn Observer> = match args.progress {
+        ProgressKind::Bar => Box::new(ProgressBar::new(io::stderr())),
+        ProgressKind::Json => Box::new(JsonLines::new(io::stdout())),
+        ProgressKind::Quiet => Box::new(Quiet),
+    };
+
+    let mut log = args.log_file.as_ref().map(|log_file| {
+        let format = match Path::new(log_file).extension() {
+            Some(ext) if ext.eq_ignore_ascii_case("csv") => LogFormat::Csv,
+            _ => LogFormat::JsonLines,
+        };
+        let file = File::create(log_file)
+            .unwrap_or_else(|e| exit_with_error(format!("could not create {}: {}", log_file, e)));
+        EpochLog::new(BufWriter::new(file), format)
+    });
+
+    // Progress may stop early without harm, for example when it is piped into `head`, but a log
+    // that could not be written completely is reported once the run is over.
+    let mut observer = |event: &Event| {
+        progress.on_event(event);
+        if let Some(log) = &mut log {
+            log.on_event(event);
+        }
+    };
+    if let Err(e) = evolve::evolve(
+        args.input_path.as_ref().unwrap(),
+        args.output_folder.as_ref().unwrap(),
+        options,
+        &mut observer,
+    ) {
+        exit_with_error(e.to_string());
+    }
+    if let (Some(log_file), Some(e)) = (&args.log_file, log.as_ref().and_then(EpochLog::error)) {
+        exit_with_error(format!("could not write {}: {}", log_file, e));
+    }
+}
+
+fn run_batch(args: &BatchArgs, options: &EvolveOptions) {
+    let input_dir = args.input_dir.as_ref().unwrap();
+    let batch_options = BatchOptions {
+        pattern: args.pattern.clone(),
+        jobs: args.jobs.unwrap_or_else(|| {
+            thread::available_parallelism().map_or(1, std::num::NonZeroUsize::get)
+        }),
+        overwrite: args.overwrite,
+    };
+
+    let start = Instant::now();
+    let results = batch::run_batch(
+        Path::new(input_dir),
+        Path::new(args.output_dir.as_ref().unwrap()),
+        options,
+        &batch_options,
+        |result| match &result.outcome {
+            Outcome::Finished {
+                num_shapes,
+                score_per_pixel,
+                elapsed,
+            } => println!(
+                "{}: {} shapes, {:.2} per pixel, {:.1}s",
+                result.image.display(),
+                num_shapes,
+                score_per_pixel,
+                elapsed.as_secs_f64()
+            ),
+            Outcome::Skipped => println!("{}: already finished, skipped", result.image.display()),
+            Outcome::Failed(e) => eprintln!("{}: failed: {}", result.image.display(), e),
+        },
     );
+    let results = match results {
+        Ok(results) => results,
+        Err(e) => exit_with_error(format!("could not read {}: {}", input_dir, e)),
+    };
+
+    let count = |f: fn(&Outcome) -> bool| results.iter().filter(|r| f(&r.outcome)).count();
+    let finished = count(|o| matches!(o, Outcome::Finished { .. }));
+    let skipped = count(|o| matches!(o, Outcome::Skipped));
+    let failed = count(|o| matches!(o, Outcome::Failed(_)));
+
+    println!();
+    println!(
+        "{} images: {} finished, {} skipped, {} failed in {:.1}s",
+        results.len(),
+        finished,
+        skipped,
+        failed,
+        start.elapsed().as_secs_f64()
+    );
+    for result in &results {
+        if let Outcome::Failed(e) = &result.outcome {
+            println!("  failed: {}: {}", result.image.display(), e);
+        }
+    }
+
+    if failed > 0 {
+        process::exit(1);
+    }
 }
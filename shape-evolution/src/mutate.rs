@@ -1,51 +1,74 @@
-use crate::random_shape;
+use crate::random_shape::{self, ShapeOptions};
 use image::Pixel;
-use rand;
 use rand::Rng;
 use std::cmp;
 
 pub trait Mutate {
-    fn mutate(&self, rng: &mut rand::rngs::ThreadRng, factor: f64) -> Self;
+    fn mutate<R: Rng + ?Sized>(&self, rng: &mut R, factor: f64, options: &ShapeOptions) -> Self;
 }
 
 pub trait BoundedMutate {
-    fn bounded_mutate(&self, rng: &mut rand::rngs::ThreadRng, max_change: i32) -> Self;
+    fn bounded_mutate<R: Rng + ?Sized>(&self, rng: &mut R, max_change: i32) -> Self;
 }
 
+// Mutated circles always keep their center on the canvas and their radius within the range allowed
+// by the shape options, so that every child can still change the image.
 impl Mutate for random_shape::RandomCircle {
-    fn mutate(&self, rng: &mut rand::rngs::ThreadRng, factor: f64) -> Self {
+    fn mutate<R: Rng + ?Sized>(&self, rng: &mut R, factor: f64, options: &ShapeOptions) -> Self {
+        let (min_radius, max_radius) = options.radius_range(self.imgx, self.imgy);
+        // Mutation ranges are based on the radius, which must not be negative.
+        let radius = cmp::max(self.radius, 0);
+        let center = self
+            .center
+            .bounded_mutate(rng, (f64::from(2 * radius) * factor) as i32);
+
         Self {
             imgx: self.imgx,
             imgy: self.imgy,
-            center: self
-                .center
-                .bounded_mutate(rng, (f64::from(2 * self.radius) * factor) as i32),
-            radius: self
-                .radius
-                .bounded_mutate(rng, (f64::from(self.radius) / 2.0 * factor) as i32),
-            color: self.color.bounded_mutate(rng, (20.0 * factor) as i32),
+            center: (
+                reflect(center.0, 0, self.imgx as i32 - 1),
+                reflect(center.1, 0, self.imgy as i32 - 1),
+            ),
+            radius: radius
+                .bounded_mutate(rng, (f64::from(radius) / 2.0 * factor) as i32)
+                .clamp(min_radius, max_radius),
+            color: options
+                .color_mode
+                .mutate_color(self.color, rng, (20.0 * factor) as i32),
         }
     }
 }
 
+// Reflects a value back into the inclusive range [min, max], as if it bounced off the ends.
+// Unlike clamping, this does not pile values up on the edges of the range.
+fn reflect(value: i32, min: i32, max: i32) -> i32 {
+    if max <= min {
+        return min;
+    }
+    let width = i64::from(max) - i64::from(min);
+    let offset = (i64::from(value) - i64::from(min)).rem_euclid(2 * width);
+    let offset = if offset <= width { offset } else { 2 * width - offset };
+    i32::try_from(i64::from(min) + offset).unwrap()
+}
+
 impl BoundedMutate for i32 {
-    fn bounded_mutate(&self, rng: &mut rand::rngs::ThreadRng, max_change: i32) -> Self {
+    fn bounded_mutate<R: Rng + ?Sized>(&self, rng: &mut R, max_change: i32) -> Self {
         let delta = rng.gen_range(-max_change..=max_change);
         *self + delta
     }
 }
 
 impl BoundedMutate for (i32, i32) {
-    fn bounded_mutate(&self, rng: &mut rand::rngs::ThreadRng, max_change: i32) -> Self {
+    fn bounded_mutate<R: Rng + ?Sized>(&self, rng: &mut R, max_change: i32) -> Self {
         let mut get_delta = || rng.gen_range(-max_change..=max_change);
         (self.0 + get_delta(), self.1 + get_delta())
     }
 }
 
 impl BoundedMutate for image::Rgba<u8> {
-    fn bounded_mutate(&self, rng: &mut rand::rngs::ThreadRng, max_change: i32) -> Self {
+    fn bounded_mutate<R: Rng + ?Sized>(&self, rng: &mut R, max_change: i32) -> Self {
         let mut get_delta = || rng.gen_range(-max_change..=max_change);
-        let clamp_channel = |c: i32| u8::try_from(cmp::max(0, cmp::min(255, c))).unwrap();
+        let clamp_channel = |c: i32| u8::try_from(c.clamp(0, 255)).unwrap();
 
         let r = clamp_channel(i32::from(self.channels()[0]) + get_delta());
         let g = clamp_channel(i32::from(self.channels()[1]) + get_delta());
@@ -54,3 +77,80 @@ impl BoundedMutate for image::Rgba<u8> {
         image::Rgba([r, g, b, 255])
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::mutate::{reflect, Mutate};
+    use crate::random_shape::{RandomCircle, RandomShape, ShapeOptions};
+    use rand::Rng;
+    use std::iter;
+
+    fn assert_valid(shape: &RandomCircle, options: &ShapeOptions) {
+        let (min_radius, max_radius) = options.radius_range(shape.imgx, shape.imgy);
+        assert!(
+            (min_radius..=max_radius).contains(&shape.radius),
+            "radius {} outside of {}..={}",
+            shape.radius,
+            min_radius,
+            max_radius
+        );
+        assert!((0..shape.imgx as i32).contains(&shape.center.0), "{:?}", shape);
+        assert!((0..shape.imgy as i32).contains(&shape.center.1), "{:?}", shape);
+        assert!(shape.get_bounds().is_some());
+    }
+
+    #[test]
+    fn test_reflect() {
+        assert_eq!(reflect(5, 0, 10), 5);
+        assert_eq!(reflect(-3, 0, 10), 3);
+        assert_eq!(reflect(13, 0, 10), 7);
+        assert_eq!(reflect(25, 0, 10), 5);
+        assert_eq!(reflect(-25, 0, 10), 5);
+        assert_eq!(reflect(7, 3, 3), 3);
+    }
+
+    #[test]
+    fn test_mutated_children_stay_valid() {
+        let mut rng = rand::thread_rng();
+        let options = ShapeOptions {
+            min_radius: 2,
+            max_radius: Some(30),
+            ..ShapeOptions::default()
+        };
+
+        for _ in 0..200 {
+            let (imgx, imgy) = (rng.gen_range(1..100), rng.gen_range(1..100));
+            let mut shape = RandomCircle::new_with_options(imgx, imgy, &options);
+            // Repeated strong mutations would quickly escape the canvas without constraints.
+            for _ in 0..50 {
+                let factor = rng.gen_range(0.0..4.0);
+                shape = shape.mutate(&mut rng, factor, &options);
+                assert_valid(&shape, &options);
+            }
+        }
+    }
+
+    #[test]
+    fn test_mutation_repairs_invalid_parents() {
+        let mut rng = rand::thread_rng();
+        let options = ShapeOptions::default();
+        let parents = [
+            ((-100, -100), 1),
+            ((1000, 20), 5),
+            ((10, 10), -4),
+            ((10, 10), 0),
+            ((10, 10), 10_000),
+        ];
+
+        for (center, radius) in parents {
+            let parent = RandomCircle {
+                center,
+                radius,
+                ..RandomCircle::new(50, 75)
+            };
+            for child in iter::repeat_with(|| parent.mutate(&mut rng, 1.0, &options)).take(100) {
+                assert_valid(&child, &options);
+            }
+        }
+    }
+}
@@ -0,0 +1,282 @@
+use crate::score::Score;
+use image::codecs::jpeg::JpegEncoder;
+use image::RgbaImage;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputFormat {
+    // PNG if the target has any transparency, JPEG otherwise.
+    #[default]
+    Auto,
+    Png,
+    Jpeg,
+}
+
+impl OutputFormat {
+    #[must_use]
+    pub fn resolve(self, has_transparency: bool) -> Self {
+        match self {
+            Self::Auto if has_transparency => Self::Png,
+            Self::Auto => Self::Jpeg,
+            format => format,
+        }
+    }
+
+    #[must_use]
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Png => "png",
+            Self::Auto | Self::Jpeg => "jpg",
+        }
+    }
+}
+
+// When to save intermediate images during a run.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Snapshots {
+    Never,
+    // After every given number of epochs.
+    Every(u32),
+    // After every epoch in which a shape was accepted.
+    Accepted,
+}
+
+impl Default for Snapshots {
+    fn default() -> Self {
+        Self::Every(20)
+    }
+}
+
+impl Snapshots {
+    // u32::is_multiple_of would need Rust 1.87.
+    #[allow(clippy::manual_is_multiple_of)]
+    #[must_use]
+    pub fn is_due(&self, epoch: u32, accepted: bool) -> bool {
+        match *self {
+            Self::Never => false,
+            Self::Every(n) => n > 0 && epoch % n == 0,
+            Self::Accepted => accepted,
+        }
+    }
+}
+
+// Parses "never", "accepted" or a number of epochs.
+impl FromStr for Snapshots {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "never" => Ok(Self::Never),
+            "accepted" => Ok(Self::Accepted),
+            _ => s.parse().map(Self::Every).map_err(|_| {
+                format!("expected \"never\", \"accepted\" or a number of epochs, got {:?}", s)
+            }),
+        }
+    }
+}
+
+// How and when the rendered image is written to disk.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OutputOptions {
+    pub format: OutputFormat,
+    pub jpeg_quality: u8,
+    pub snapshots: Snapshots,
+    // File name of snapshots without the extension. "{epoch}", "{score}" and "{shapes}" are
+    // replaced by the current epoch, score and number of shapes.
+    pub snapshot_name: String,
+    // File name of the final image without the extension.
+    pub final_name: String,
+    // Size of the saved images. If only one is given the other keeps the aspect ratio of the
+    // input, and if neither is given the images have the size of the input.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub width: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub height: Option<u32>,
+}
+
+impl Default for OutputOptions {
+    fn default() -> Self {
+        Self {
+            format: OutputFormat::Auto,
+            jpeg_quality: 75,
+            snapshots: Snapshots::default(),
+            snapshot_name: "out-{epoch}-{score}".to_string(),
+            final_name: "out".to_string(),
+            width: None,
+            height: None,
+        }
+    }
+}
+
+impl OutputOptions {
+    // Returns the factor by which shapes evolved on the working image are scaled up when
+    // rendering the output, along with the size of the rendered image. Circles cannot be
+    // stretched, so if both a width and a height are requested the image is rendered with the
+    // aspect ratio of the input and resized to the requested size when it is saved.
+    #[must_use]
+    pub fn render_size(&self, working_size: (u32, u32), input_size: (u32, u32)) -> (f64, (u32, u32)) {
+        let (width, height) = (f64::from(working_size.0), f64::from(working_size.1));
+        let scale = match (self.width, self.height) {
+            (None, None) => return (f64::from(input_size.0) / width, input_size),
+            (Some(w), None) => f64::from(w) / width,
+            (None, Some(h)) => f64::from(h) / height,
+            (Some(w), Some(h)) => f64::max(f64::from(w) / width, f64::from(h) / height),
+        };
+        (scale, ((width * scale).round() as u32, (height * scale).round() as u32))
+    }
+
+    #[must_use]
+    pub fn saved_size(&self, rendered_size: (u32, u32)) -> (u32, u32) {
+        match (self.width, self.height) {
+            (Some(w), Some(h)) => (w, h),
+            _ => rendered_size,
+        }
+    }
+
+    // Returns the paths the final image may have been saved to by a previous run.
+    #[must_use]
+    pub fn final_paths(&self, output_folder: &Path) -> Vec<PathBuf> {
+        [OutputFormat::Png, OutputFormat::Jpeg]
+            .iter()
+            .filter(|&&format| self.format == OutputFormat::Auto || self.format == format)
+            .map(|&format| output_folder.join(format!("{}.{}", self.final_name, format.extension())))
+            .collect()
+    }
+}
+
+// Fills in the placeholders of a file name template and appends the extension of the format.
+#[must_use]
+pub fn file_name(template: &str, format: OutputFormat, epoch: u32, score: Score, shapes: u32) -> String {
+    let name = template
+        .replace("{epoch}", &epoch.to_string())
+        .replace("{score}", &score.to_string())
+        .replace("{shapes}", &shapes.to_string());
+    format!("{}.{}", name, format.extension())
+}
+
+// Copies the alpha channel of the target onto the output, so that transparent areas of the
+// target stay transparent in the exported image.
+#[must_use]
+pub fn apply_alpha_mask(output_img: &RgbaImage, target_img: &RgbaImage) -> RgbaImage {
+    let mut masked = output_img.clone();
+    for (out, target) in masked.pixels_mut().zip(target_img.pixels()) {
+        out[3] = ((u16::from(out[3]) * u16::from(target[3])) / 255) as u8;
+    }
+    masked
+}
+
+// Saves the image in the given format, which must not be Auto.
+pub fn save_image(
+    img: &RgbaImage,
+    path: &Path,
+    format: OutputFormat,
+    jpeg_quality: u8,
+) -> image::ImageResult<()> {
+    match format {
+        OutputFormat::Png => img.save_with_format(path, image::ImageFormat::Png),
+        OutputFormat::Auto | OutputFormat::Jpeg => {
+            let rgb = image::DynamicImage::ImageRgba8(img.clone()).to_rgb8();
+            let mut writer = BufWriter::new(File::create(path)?);
+            JpegEncoder::new_with_quality(&mut writer, jpeg_quality).encode_image(&rgb)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::score::Score;
+    use crate::output::{apply_alpha_mask, file_name, OutputFormat, OutputOptions, Snapshots};
+    use image::RgbaImage;
+    use std::path::{Path, PathBuf};
+
+    #[test]
+    fn test_alpha_mask_keeps_transparency() {
+        let output_img = RgbaImage::from_fn(4, 4, |_x, _y| image::Rgba([10, 20, 30, 255]));
+        let target_img =
+            RgbaImage::from_fn(4, 4, |x, _y| image::Rgba([0, 0, 0, if x < 2 { 0 } else { 255 }]));
+
+        let masked = apply_alpha_mask(&output_img, &target_img);
+        assert_eq!(masked.get_pixel(0, 0), &image::Rgba([10, 20, 30, 0]));
+        assert_eq!(masked.get_pixel(3, 3), &image::Rgba([10, 20, 30, 255]));
+    }
+
+    #[test]
+    fn test_snapshots() {
+        assert_eq!("never".parse(), Ok(Snapshots::Never));
+        assert_eq!("accepted".parse(), Ok(Snapshots::Accepted));
+        assert_eq!("15".parse(), Ok(Snapshots::Every(15)));
+        assert!("sometimes".parse::<Snapshots>().is_err());
+
+        assert!(Snapshots::Every(20).is_due(40, false));
+        assert!(!Snapshots::Every(20).is_due(41, true));
+        assert!(!Snapshots::Every(0).is_due(40, true));
+        assert!(Snapshots::Accepted.is_due(41, true));
+        assert!(!Snapshots::Never.is_due(40, true));
+    }
+
+    #[test]
+    fn test_file_name() {
+        let options = OutputOptions {
+            snapshot_name: "frame-{shapes}_{epoch}-{score}".to_string(),
+            ..OutputOptions::default()
+        };
+        assert_eq!(
+            file_name(&options.snapshot_name, OutputFormat::Png, 40, Score(1234), 38),
+            "frame-38_40-1234.png"
+        );
+        assert_eq!(
+            file_name(&options.final_name, OutputFormat::Jpeg, 40, Score(1234), 38),
+            "out.jpg"
+        );
+    }
+
+    #[test]
+    fn test_render_size() {
+        let options = OutputOptions::default();
+        assert_eq!(options.render_size((100, 50), (400, 200)), (4.0, (400, 200)));
+
+        let options = OutputOptions {
+            width: Some(1000),
+            ..OutputOptions::default()
+        };
+        assert_eq!(options.render_size((100, 50), (400, 200)), (10.0, (1000, 500)));
+
+        let options = OutputOptions {
+            height: Some(100),
+            ..OutputOptions::default()
+        };
+        assert_eq!(options.render_size((100, 50), (400, 200)), (2.0, (200, 100)));
+
+        let options = OutputOptions {
+            width: Some(300),
+            height: Some(300),
+            ..OutputOptions::default()
+        };
+        let (scale, rendered) = options.render_size((100, 50), (400, 200));
+        assert_eq!((scale, rendered), (6.0, (600, 300)));
+        assert_eq!(options.saved_size(rendered), (300, 300));
+    }
+
+    #[test]
+    fn test_final_paths() {
+        let options = OutputOptions::default();
+        assert_eq!(
+            options.final_paths(Path::new("a")),
+            [PathBuf::from("a/out.png"), PathBuf::from("a/out.jpg")]
+        );
+
+        let options = OutputOptions {
+            format: OutputFormat::Png,
+            final_name: "final".to_string(),
+            ..OutputOptions::default()
+        };
+        assert_eq!(options.final_paths(Path::new("a")), [PathBuf::from("a/final.png")]);
+    }
+}
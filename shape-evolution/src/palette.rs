@@ -0,0 +1,376 @@
+use crate::mutate::BoundedMutate;
+use image::Rgba;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+// Restricts the colors that shapes may be drawn with.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ColorMode {
+    // Any color in the RGB cube.
+    #[default]
+    Full,
+    // Only shades of gray.
+    Grayscale,
+    // Only the colors of a fixed palette.
+    Palette(Palette),
+    // A palette of the given size, extracted from the target image with `resolve`. Until it has
+    // been resolved this mode behaves like `Full`.
+    Extracted(usize),
+}
+
+impl ColorMode {
+    // Replaces an `Extracted` mode by the palette extracted from the target image. All other
+    // modes are returned unchanged.
+    #[must_use]
+    pub fn resolve(self, target_img: &image::RgbaImage) -> Self {
+        match self {
+            Self::Extracted(size) => Self::Palette(Palette::median_cut(target_img, size)),
+            mode => mode,
+        }
+    }
+
+    pub fn random_color<R: Rng + ?Sized>(&self, rng: &mut R) -> Rgba<u8> {
+        match self {
+            Self::Full | Self::Extracted(_) => Rgba([
+                rng.gen_range(0..=255),
+                rng.gen_range(0..=255),
+                rng.gen_range(0..=255),
+                255,
+            ]),
+            Self::Grayscale => {
+                let luma = rng.gen_range(0..=255);
+                Rgba([luma, luma, luma, 255])
+            }
+            Self::Palette(palette) => palette.colors[rng.gen_range(0..palette.colors.len())],
+        }
+    }
+
+    pub fn mutate_color<R: Rng + ?Sized>(
+        &self,
+        color: Rgba<u8>,
+        rng: &mut R,
+        max_change: i32,
+    ) -> Rgba<u8> {
+        match self {
+            Self::Full | Self::Extracted(_) => color.bounded_mutate(rng, max_change),
+            Self::Grayscale => {
+                let luma = i32::from(color[0]) + rng.gen_range(-max_change..=max_change);
+                let luma = u8::try_from(luma.clamp(0, 255)).unwrap();
+                Rgba([luma, luma, luma, 255])
+            }
+            // Palette entries are usually much further apart than max_change, so mutating
+            // freely and snapping back onto the palette would nearly always keep the color. Some
+            // mutations jump to another entry instead.
+            Self::Palette(palette) => {
+                if palette.colors.len() > 1 && rng.gen_bool(PALETTE_JUMP_PROBABILITY) {
+                    palette.random_other(color, rng)
+                } else {
+                    palette.nearest(color.bounded_mutate(rng, max_change))
+                }
+            }
+        }
+    }
+}
+
+// The chance that mutating a color in palette mode picks a different entry of the palette.
+const PALETTE_JUMP_PROBABILITY: f64 = 0.25;
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct PaletteError(String);
+
+impl fmt::Display for PaletteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid palette: {}", self.0)
+    }
+}
+
+impl std::error::Error for PaletteError {}
+
+// A non-empty list of opaque colors.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "Vec<[u8; 3]>", into = "Vec<[u8; 3]>")]
+pub struct Palette {
+    colors: Vec<Rgba<u8>>,
+}
+
+impl TryFrom<Vec<[u8; 3]>> for Palette {
+    type Error = PaletteError;
+
+    fn try_from(colors: Vec<[u8; 3]>) -> Result<Self, Self::Error> {
+        Self::new(colors.into_iter().map(|[r, g, b]| Rgba([r, g, b, 255])).collect())
+    }
+}
+
+impl From<Palette> for Vec<[u8; 3]> {
+    fn from(palette: Palette) -> Self {
+        palette.colors.iter().map(|c| [c[0], c[1], c[2]]).collect()
+    }
+}
+
+impl Palette {
+    pub fn new(colors: Vec<Rgba<u8>>) -> Result<Self, PaletteError> {
+        if colors.is_empty() {
+            return Err(PaletteError("palette has no colors".to_string()));
+        }
+        let colors = colors.into_iter().map(|c| Rgba([c[0], c[1], c[2], 255])).collect();
+        Ok(Self { colors })
+    }
+
+    #[must_use]
+    pub fn colors(&self) -> &[Rgba<u8>] {
+        &self.colors
+    }
+
+    // Parses either a GIMP palette (GPL) file or a list of hex colors, one per line.
+    pub fn parse(text: &str) -> Result<Self, PaletteError> {
+        if text.trim_start().starts_with("GIMP Palette") {
+            Self::parse_gpl(text)
+        } else {
+            Self::parse_hex(text)
+        }
+    }
+
+    // Parses a GIMP palette file. Each color line starts with three decimal channel values,
+    // optionally followed by a name.
+    pub fn parse_gpl(text: &str) -> Result<Self, PaletteError> {
+        let mut lines = text.lines();
+        if lines.next().map(str::trim) != Some("GIMP Palette") {
+            return Err(PaletteError("missing \"GIMP Palette\" header".to_string()));
+        }
+
+        let mut colors = Vec::new();
+        for line in lines {
+            let line = line.trim();
+            if line.is_empty()
+                || line.starts_with('#')
+                || line.starts_with("Name:")
+                || line.starts_with("Columns:")
+            {
+                continue;
+            }
+
+            let channels: Vec<u8> = line
+                .split_whitespace()
+                .take(3)
+                .map(str::parse)
+                .collect::<Result<_, _>>()
+                .map_err(|_| PaletteError(format!("could not parse line {:?}", line)))?;
+            if channels.len() != 3 {
+                return Err(PaletteError(format!("could not parse line {:?}", line)));
+            }
+            colors.push(Rgba([channels[0], channels[1], channels[2], 255]));
+        }
+        Self::new(colors)
+    }
+
+    // Parses a list of `RRGGBB` or `#RRGGBB` colors. Blank lines and lines starting with `;` or
+    // `//` are ignored.
+    pub fn parse_hex(text: &str) -> Result<Self, PaletteError> {
+        let mut colors = Vec::new();
+        for line in text.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with(';') || line.starts_with("//") {
+                continue;
+            }
+
+            let hex = line.strip_prefix('#').unwrap_or(line);
+            let value = if hex.len() == 6 {
+                u32::from_str_radix(hex, 16).ok()
+            } else {
+                None
+            };
+            let value = value.ok_or_else(|| PaletteError(format!("invalid hex color {:?}", line)))?;
+            let [_, r, g, b] = value.to_be_bytes();
+            colors.push(Rgba([r, g, b, 255]));
+        }
+        Self::new(colors)
+    }
+
+    // Extracts a palette of at most `size` colors from the image using the median cut algorithm.
+    // Fully transparent pixels are ignored.
+    #[must_use]
+    pub fn median_cut(img: &image::RgbaImage, size: usize) -> Self {
+        let pixels: Vec<[u8; 3]> = img
+            .pixels()
+            .filter(|p| p[3] > 0)
+            .map(|p| [p[0], p[1], p[2]])
+            .collect();
+        if pixels.is_empty() || size == 0 {
+            return Self::new(vec![Rgba([0, 0, 0, 255])]).unwrap();
+        }
+
+        let mut boxes = vec![pixels];
+        while boxes.len() < size {
+            // Split the box with the widest channel range at the median of that channel.
+            let (index, channel, range) = boxes
+                .iter()
+                .enumerate()
+                .map(|(i, b)| {
+                    let (channel, range) = Self::widest_channel(b);
+                    (i, channel, range)
+                })
+                .max_by_key(|&(_, _, range)| range)
+                .unwrap();
+            if range == 0 {
+                break;
+            }
+
+            let mut split = boxes.swap_remove(index);
+            split.sort_unstable_by_key(|p| p[channel]);
+            let upper = split.split_off(split.len() / 2);
+            boxes.push(split);
+            boxes.push(upper);
+        }
+
+        let colors = boxes
+            .iter()
+            .map(|b| {
+                let mut sum = [0u64; 3];
+                for p in b {
+                    for (s, &c) in sum.iter_mut().zip(p) {
+                        *s += u64::from(c);
+                    }
+                }
+                let len = b.len() as u64;
+                let average = |c: usize| u8::try_from(sum[c] / len).unwrap();
+                Rgba([average(0), average(1), average(2), 255])
+            })
+            .collect();
+        Self::new(colors).unwrap()
+    }
+
+    fn widest_channel(pixels: &[[u8; 3]]) -> (usize, u8) {
+        (0..3)
+            .map(|c| {
+                let min = pixels.iter().map(|p| p[c]).min().unwrap_or(0);
+                let max = pixels.iter().map(|p| p[c]).max().unwrap_or(0);
+                (c, max - min)
+            })
+            .max_by_key(|&(_, range)| range)
+            .unwrap()
+    }
+
+    // Returns a random entry of the palette other than color, or color itself if the palette has
+    // no other entries.
+    pub fn random_other<R: Rng + ?Sized>(&self, color: Rgba<u8>, rng: &mut R) -> Rgba<u8> {
+        let others: Vec<_> = self.colors.iter().filter(|&&c| c != color).collect();
+        if others.is_empty() {
+            return color;
+        }
+        *others[rng.gen_range(0..others.len())]
+    }
+
+    // Returns the palette color closest to the given color.
+    #[must_use]
+    pub fn nearest(&self, color: Rgba<u8>) -> Rgba<u8> {
+        *self
+            .colors
+            .iter()
+            .min_by_key(|c| {
+                (0..3)
+                    .map(|i| {
+                        let d = i32::from(c[i]) - i32::from(color[i]);
+                        d * d
+                    })
+                    .sum::<i32>()
+            })
+            .unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::palette::{ColorMode, Palette};
+    use image::{Rgba, RgbaImage};
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_parse_gpl() {
+        let text = "GIMP Palette\nName: Test\nColumns: 2\n#\n255   0   0\tRed\n  0 128 255\tBlue\n";
+        let palette = Palette::parse(text).unwrap();
+        assert_eq!(
+            palette.colors(),
+            &[Rgba([255, 0, 0, 255]), Rgba([0, 128, 255, 255])]
+        );
+    }
+
+    #[test]
+    fn test_parse_hex() {
+        let text = "; retro\n#ff0000\n00ff7f\n\n";
+        let palette = Palette::parse(text).unwrap();
+        assert_eq!(
+            palette.colors(),
+            &[Rgba([255, 0, 0, 255]), Rgba([0, 255, 127, 255])]
+        );
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert!(Palette::parse("#ff00").is_err());
+        assert!(Palette::parse("").is_err());
+        assert!(Palette::parse("GIMP Palette\n1 2\n").is_err());
+    }
+
+    #[test]
+    fn test_median_cut_two_colors() {
+        let img = RgbaImage::from_fn(10, 10, |x, _y| {
+            if x < 5 {
+                Rgba([200, 10, 10, 255])
+            } else {
+                Rgba([10, 10, 200, 255])
+            }
+        });
+        let palette = Palette::median_cut(&img, 4);
+        assert_eq!(palette.colors().len(), 2);
+        assert!(palette.colors().contains(&Rgba([200, 10, 10, 255])));
+        assert!(palette.colors().contains(&Rgba([10, 10, 200, 255])));
+    }
+
+    #[test]
+    fn test_mutate_stays_in_palette() {
+        let palette = Palette::parse("#000000\n#808080\n#ffffff").unwrap();
+        let mode = ColorMode::Palette(palette.clone());
+        let mut rng = ChaCha8Rng::seed_from_u64(1);
+
+        let mut color = mode.random_color(&mut rng);
+        for _ in 0..1000 {
+            color = mode.mutate_color(color, &mut rng, 100);
+            assert!(palette.colors().contains(&color));
+        }
+    }
+
+    #[test]
+    fn test_mutate_stays_gray() {
+        let mode = ColorMode::Grayscale;
+        let mut rng = ChaCha8Rng::seed_from_u64(1);
+
+        let mut color = mode.random_color(&mut rng);
+        for _ in 0..1000 {
+            color = mode.mutate_color(color, &mut rng, 20);
+            assert!(color[0] == color[1] && color[1] == color[2]);
+        }
+    }
+
+    #[test]
+    fn test_mutate_changes_sparse_palette_entry() {
+        let palette = Palette::parse(
+            "#000000\n#ff0000\n#00ff00\n#0000ff\n#ffff00\n#ff00ff\n#00ffff\n#ffffff",
+        )
+        .unwrap();
+        let mode = ColorMode::Palette(palette.clone());
+        let mut rng = ChaCha8Rng::seed_from_u64(1);
+
+        let start = Rgba([0, 0, 0, 255]);
+        let mut seen = HashSet::new();
+        for _ in 0..100 {
+            let color = mode.mutate_color(start, &mut rng, 20);
+            assert!(palette.colors().contains(&color));
+            seen.insert(color);
+        }
+        assert!(seen.len() > 2);
+    }
+}
@@ -0,0 +1,451 @@
+use crate::random_shape::RandomCircle;
+use crate::score::Score;
+use serde::{Serialize, Serializer};
+use std::io::{self, Write};
+use std::time::Duration;
+
+// Progress of an evolution run, reported to an Observer as it happens.
+#[derive(Debug, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event<'a> {
+    EpochStarted {
+        epoch: u32,
+        num_epochs: u32,
+    },
+    // Sent once for every shape accepted during the epoch.
+    ShapeAccepted {
+        epoch: u32,
+        shape: &'a RandomCircle,
+    },
+    // Sent when an epoch found no shape which improves the image.
+    EpochRejected {
+        epoch: u32,
+    },
+    EpochFinished {
+        epoch: u32,
+        num_epochs: u32,
+        // Number of generations evolved during the epoch.
+        num_gens: u32,
+        // Number of shapes accepted since the start of the run.
+        num_shapes: u32,
+        score: Score,
+        score_per_pixel: f64,
+        #[serde(rename = "elapsed_ms", serialize_with = "serialize_millis")]
+        elapsed: Duration,
+    },
+}
+
+fn serialize_millis<S>(duration: &Duration, ser: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    ser.serialize_f64(duration.as_secs_f64() * 1000.0)
+}
+
+pub trait Observer {
+    fn on_event(&mut self, event: &Event);
+}
+
+// Any closure taking an event can be used as a progress callback.
+impl<F> Observer for F
+where
+    F: FnMut(&Event),
+{
+    fn on_event(&mut self, event: &Event) {
+        self(event);
+    }
+}
+
+// Ignores all events.
+pub struct Quiet;
+
+impl Observer for Quiet {
+    fn on_event(&mut self, _event: &Event) {}
+}
+
+// Passes every event on to each of the observers in turn.
+pub struct Observers(pub Vec<Box<dyn Observer>>);
+
+impl Observer for Observers {
+    fn on_event(&mut self, event: &Event) {
+        for observer in &mut self.0 {
+            observer.on_event(event);
+        }
+    }
+}
+
+// The writer of an observer. It keeps the first error instead of returning it, and skips all
+// writes after that, so that a closed pipe or a full disk ends the output but not the run.
+struct Output<W: Write> {
+    writer: W,
+    error: Option<io::Error>,
+}
+
+impl<W: Write> Output<W> {
+    fn new(writer: W) -> Self {
+        Self {
+            writer,
+            error: None,
+        }
+    }
+
+    fn write_with(&mut self, write: impl FnOnce(&mut W) -> io::Result<()>) {
+        if self.error.is_none() {
+            self.error = write(&mut self.writer).err();
+        }
+    }
+}
+
+// Writes every event as a line of JSON.
+pub struct JsonLines<W: Write> {
+    output: Output<W>,
+}
+
+impl<W: Write> JsonLines<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            output: Output::new(writer),
+        }
+    }
+
+    // The first error writing an event. No more events are written after it.
+    pub fn error(&self) -> Option<&io::Error> {
+        self.output.error.as_ref()
+    }
+}
+
+impl<W: Write> Observer for JsonLines<W> {
+    fn on_event(&mut self, event: &Event) {
+        self.output.write_with(|writer| {
+            serde_json::to_writer(&mut *writer, event)?;
+            writeln!(writer)
+        });
+    }
+}
+
+// Draws a single progress bar line which is redrawn after every epoch.
+pub struct ProgressBar<W: Write> {
+    output: Output<W>,
+    width: usize,
+}
+
+impl<W: Write> ProgressBar<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            output: Output::new(writer),
+            width: 30,
+        }
+    }
+
+    // The first error drawing the bar. It is not drawn again after it.
+    pub fn error(&self) -> Option<&io::Error> {
+        self.output.error.as_ref()
+    }
+}
+
+impl<W: Write> Observer for ProgressBar<W> {
+    fn on_event(&mut self, event: &Event) {
+        if let Event::EpochFinished {
+            epoch,
+            num_epochs,
+            num_shapes,
+            score_per_pixel,
+            elapsed,
+            ..
+        } = *event
+        {
+            let width = self.width;
+            let filled = (width * epoch as usize / num_epochs.max(1) as usize).min(width);
+            self.output.write_with(|writer| {
+                write!(
+                    writer,
+                    "\r[{}{}] {}/{} epochs, {} shapes, {:.2} per pixel, {:.1}s",
+                    "#".repeat(filled),
+                    "-".repeat(width - filled),
+                    epoch,
+                    num_epochs,
+                    num_shapes,
+                    score_per_pixel,
+                    elapsed.as_secs_f64()
+                )?;
+                if epoch == num_epochs {
+                    writeln!(writer)?;
+                }
+                writer.flush()
+            });
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum LogFormat {
+    Csv,
+    JsonLines,
+}
+
+// A single line of an EpochLog.
+#[derive(Serialize)]
+struct EpochRecord<'a> {
+    epoch: u32,
+    accepted: bool,
+    shapes: &'a [RandomCircle],
+    num_shapes: u32,
+    score: Score,
+    score_per_pixel: f64,
+    generations: u32,
+    elapsed_ms: f64,
+}
+
+// Writes one record per epoch, so that the convergence of different runs can be compared.
+pub struct EpochLog<W: Write> {
+    output: Output<W>,
+    format: LogFormat,
+    shapes: Vec<RandomCircle>,
+}
+
+impl<W: Write> EpochLog<W> {
+    pub fn new(writer: W, format: LogFormat) -> Self {
+        let mut output = Output::new(writer);
+        if let LogFormat::Csv = format {
+            output.write_with(|writer| {
+                writeln!(
+                    writer,
+                    "epoch,accepted,shapes,num_shapes,score,score_per_pixel,generations,elapsed_ms"
+                )
+            });
+        }
+        Self {
+            output,
+            format,
+            shapes: Vec::new(),
+        }
+    }
+
+    // The first error writing the log. Nothing more is written after it, so the log is
+    // incomplete.
+    pub fn error(&self) -> Option<&io::Error> {
+        self.output.error.as_ref()
+    }
+
+    fn write_record(&mut self, record: &EpochRecord) {
+        match self.format {
+            LogFormat::Csv => {
+                // Shapes are written as "x y radius rrggbbaa", separated by semicolons.
+                let shapes: Vec<String> = record
+                    .shapes
+                    .iter()
+                    .map(|s| {
+                        format!(
+                            "{} {} {} {:02x}{:02x}{:02x}{:02x}",
+                            s.center.0,
+                            s.center.1,
+                            s.radius,
+                            s.color[0],
+                            s.color[1],
+                            s.color[2],
+                            s.color[3]
+                        )
+                    })
+                    .collect();
+                self.output.write_with(|writer| {
+                    writeln!(
+                        writer,
+                        "{},{},{},{},{},{},{},{}",
+                        record.epoch,
+                        record.accepted,
+                        shapes.join(";"),
+                        record.num_shapes,
+                        record.score,
+                        record.score_per_pixel,
+                        record.generations,
+                        record.elapsed_ms
+                    )
+                });
+            }
+            LogFormat::JsonLines => self.output.write_with(|writer| {
+                serde_json::to_writer(&mut *writer, record)?;
+                writeln!(writer)
+            }),
+        }
+    }
+}
+
+impl<W: Write> Observer for EpochLog<W> {
+    fn on_event(&mut self, event: &Event) {
+        match *event {
+            Event::EpochStarted { .. } => self.shapes.clear(),
+            Event::ShapeAccepted { shape, .. } => self.shapes.push(shape.clone()),
+            Event::EpochRejected { .. } => {}
+            Event::EpochFinished {
+                epoch,
+                num_gens,
+                num_shapes,
+                score,
+                score_per_pixel,
+                elapsed,
+                ..
+            } => {
+                let shapes = std::mem::take(&mut self.shapes);
+                self.write_record(&EpochRecord {
+                    epoch,
+                    accepted: !shapes.is_empty(),
+                    shapes: &shapes,
+                    num_shapes,
+                    score,
+                    score_per_pixel,
+                    generations: num_gens,
+                    elapsed_ms: elapsed.as_secs_f64() * 1000.0,
+                });
+                self.output.write_with(Write::flush);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::progress::{EpochLog, Event, JsonLines, LogFormat, Observer, ProgressBar};
+    use crate::random_shape::RandomCircle;
+    use crate::score::Score;
+    use std::io::{self, Write};
+    use std::time::Duration;
+
+    fn finished(epoch: u32) -> Event<'static> {
+        Event::EpochFinished {
+            epoch,
+            num_epochs: 4,
+            num_gens: 50,
+            num_shapes: 3,
+            score: Score(1000),
+            score_per_pixel: 2.5,
+            elapsed: Duration::from_millis(1500),
+        }
+    }
+
+    #[test]
+    fn test_json_lines() {
+        let mut out = Vec::new();
+        let mut observer = JsonLines::new(&mut out);
+        observer.on_event(&Event::EpochRejected { epoch: 1 });
+        observer.on_event(&finished(1));
+
+        let out = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines[0], r#"{"event":"epoch_rejected","epoch":1}"#);
+        assert_eq!(
+            lines[1],
+            r#"{"event":"epoch_finished","epoch":1,"num_epochs":4,"num_gens":50,"num_shapes":3,"score":1000,"score_per_pixel":2.5,"elapsed_ms":1500.0}"#
+        );
+    }
+
+    #[test]
+    fn test_progress_bar() {
+        let mut out = Vec::new();
+        let mut observer = ProgressBar::new(&mut out);
+        observer.on_event(&finished(2));
+
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains(&format!("[{}{}] 2/4 epochs", "#".repeat(15), "-".repeat(15))));
+    }
+
+    #[test]
+    fn test_progress_bar_past_last_epoch() {
+        let mut out = Vec::new();
+        let mut observer = ProgressBar::new(&mut out);
+        observer.on_event(&finished(5));
+
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains(&format!("[{}] 5/4 epochs", "#".repeat(30))));
+    }
+
+    // Fails every write, like stdout piped into a program that has exited.
+    struct ClosedPipe;
+
+    impl Write for ClosedPipe {
+        fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+            Err(io::ErrorKind::BrokenPipe.into())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Err(io::ErrorKind::BrokenPipe.into())
+        }
+    }
+
+    #[test]
+    fn test_write_errors_are_kept() {
+        let mut json = JsonLines::new(ClosedPipe);
+        let mut bar = ProgressBar::new(ClosedPipe);
+        let mut log = EpochLog::new(ClosedPipe, LogFormat::Csv);
+        for epoch in 1..=4 {
+            json.on_event(&finished(epoch));
+            bar.on_event(&finished(epoch));
+            log.on_event(&finished(epoch));
+        }
+        assert_eq!(json.error().unwrap().kind(), io::ErrorKind::BrokenPipe);
+        assert_eq!(bar.error().unwrap().kind(), io::ErrorKind::BrokenPipe);
+        assert_eq!(log.error().unwrap().kind(), io::ErrorKind::BrokenPipe);
+    }
+
+    #[test]
+    fn test_closure_observer() {
+        let mut count = 0;
+        let mut observer = |_event: &Event| count += 1;
+        observer.on_event(&Event::EpochRejected { epoch: 1 });
+        observer.on_event(&finished(1));
+        assert_eq!(count, 2);
+    }
+
+    fn log_epochs(format: LogFormat) -> String {
+        let shape = RandomCircle {
+            imgx: 10,
+            imgy: 10,
+            center: (4, 5),
+            radius: 3,
+            color: image::Rgba([255, 128, 0, 255]),
+        };
+
+        let mut out = Vec::new();
+        let mut log = EpochLog::new(&mut out, format);
+        log.on_event(&Event::EpochStarted {
+            epoch: 1,
+            num_epochs: 4,
+        });
+        log.on_event(&Event::ShapeAccepted {
+            epoch: 1,
+            shape: &shape,
+        });
+        log.on_event(&finished(1));
+        log.on_event(&Event::EpochStarted {
+            epoch: 2,
+            num_epochs: 4,
+        });
+        log.on_event(&Event::EpochRejected { epoch: 2 });
+        log.on_event(&finished(2));
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_epoch_log_csv() {
+        let out = log_epochs(LogFormat::Csv);
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(
+            lines,
+            [
+                "epoch,accepted,shapes,num_shapes,score,score_per_pixel,generations,elapsed_ms",
+                "1,true,4 5 3 ff8000ff,3,1000,2.5,50,1500",
+                "2,false,,3,1000,2.5,50,1500",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_epoch_log_json_lines() {
+        let out = log_epochs(LogFormat::JsonLines);
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(
+            lines[0],
+            r#"{"epoch":1,"accepted":true,"shapes":[{"imgx":10,"imgy":10,"center":[4,5],"radius":3,"color":[255,128,0,255]}],"num_shapes":3,"score":1000,"score_per_pixel":2.5,"generations":50,"elapsed_ms":1500.0}"#
+        );
+        assert!(lines[1].starts_with(r#"{"epoch":2,"accepted":false,"shapes":[],"#));
+    }
+}
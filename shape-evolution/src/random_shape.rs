@@ -1,7 +1,11 @@
 use crate::image_diff::image_diff;
 use crate::mutate::Mutate;
+use crate::palette::ColorMode;
+use crate::render::fill_circle_antialiased;
+use crate::score::Score;
+use crate::span::span_diff;
 use image::GenericImageView;
-use image::{Pixel, Rgba};
+use image::Rgba;
 use rand::Rng;
 use serde::{
     de::Deserializer,
@@ -21,6 +25,115 @@ pub struct BoundingBox {
     pub height: u32,
 }
 
+impl BoundingBox {
+    #[must_use]
+    pub fn intersects(&self, other: &Self) -> bool {
+        self.x < other.x + other.width
+            && other.x < self.x + self.width
+            && self.y < other.y + other.height
+            && other.y < self.y + self.height
+    }
+}
+
+// Constraints applied when generating and mutating shapes. Radii are measured in pixels of the
+// target image the shapes are evolved against.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ShapeOptions {
+    pub color_mode: ColorMode,
+    pub min_radius: u32,
+    // Defaults to the largest dimension of the image when not set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_radius: Option<u32>,
+    pub size_schedule: SizeSchedule,
+}
+
+impl Default for ShapeOptions {
+    fn default() -> Self {
+        Self {
+            color_mode: ColorMode::Full,
+            min_radius: 1,
+            max_radius: None,
+            size_schedule: SizeSchedule::Constant,
+        }
+    }
+}
+
+impl ShapeOptions {
+    // Returns the inclusive range of radii allowed for shapes on an image of the given size.
+    #[must_use]
+    pub fn radius_range(&self, imgx: u32, imgy: u32) -> (i32, i32) {
+        let max_radius = self
+            .max_radius
+            .unwrap_or_else(|| cmp::max(imgx, imgy).saturating_sub(1));
+        let min_radius = i32::try_from(self.min_radius).unwrap_or(i32::MAX);
+        let max_radius = i32::try_from(max_radius).unwrap_or(i32::MAX);
+        (min_radius, cmp::max(min_radius, max_radius))
+    }
+
+    // Returns the options to use once `num_shapes` shapes have been accepted, with the maximum
+    // radius reduced according to the size schedule.
+    #[must_use]
+    pub fn for_shape_count(&self, imgx: u32, imgy: u32, num_shapes: u32) -> Self {
+        let (_, max_radius) = self.radius_range(imgx, imgy);
+        let max_radius = self
+            .size_schedule
+            .max_radius(max_radius.try_into().unwrap(), num_shapes);
+        Self {
+            max_radius: Some(cmp::max(max_radius, self.min_radius)),
+            size_schedule: SizeSchedule::Constant,
+            ..self.clone()
+        }
+    }
+}
+
+// Shrinks the maximum radius of new shapes as accepted shapes accumulate, so that the image is
+// first laid out with coarse shapes and then refined with smaller ones.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SizeSchedule {
+    // The maximum radius never changes.
+    #[default]
+    Constant,
+    // The maximum radius shrinks linearly, reaching `final_max_radius` after `num_shapes`
+    // accepted shapes.
+    Linear { final_max_radius: u32, num_shapes: u32 },
+    // The distance between the maximum radius and `final_max_radius` halves every `half_life`
+    // accepted shapes.
+    Exponential { final_max_radius: u32, half_life: u32 },
+}
+
+impl SizeSchedule {
+    #[must_use]
+    pub fn max_radius(&self, initial_max_radius: u32, num_shapes: u32) -> u32 {
+        match *self {
+            Self::Constant => initial_max_radius,
+            Self::Linear {
+                final_max_radius,
+                num_shapes: length,
+            } => {
+                if num_shapes >= length || final_max_radius >= initial_max_radius {
+                    return cmp::min(final_max_radius, initial_max_radius);
+                }
+                let shrink = u64::from(initial_max_radius - final_max_radius) * u64::from(num_shapes)
+                    / u64::from(length);
+                initial_max_radius - u32::try_from(shrink).unwrap()
+            }
+            Self::Exponential {
+                final_max_radius,
+                half_life,
+            } => {
+                if final_max_radius >= initial_max_radius {
+                    return initial_max_radius;
+                }
+                let remaining = f64::from(initial_max_radius - final_max_radius)
+                    * 0.5_f64.powf(f64::from(num_shapes) / f64::from(cmp::max(half_life, 1)));
+                final_max_radius + remaining.round() as u32
+            }
+        }
+    }
+}
+
 pub trait RandomShape: Mutate {
     #[must_use]
     fn draw(&self, image: &image::RgbaImage) -> image::RgbaImage;
@@ -40,9 +153,14 @@ pub trait RandomShape: Mutate {
         &self,
         target_img: &image::RgbaImage,
         current_img: &image::RgbaImage,
-    ) -> i128;
+    ) -> i64;
 
     fn scale_up(&self, scale: f64) -> Self;
+
+    // Draws the shape onto an image `scale` times as large as the one it was evolved on, with
+    // anti-aliased edges. This is slower than draw and does not match how shapes are scored, so
+    // it is only used for output.
+    fn render(&self, image: &mut image::RgbaImage, scale: f64);
 }
 
 // Serializer and deserializer for an Rgba<u8> struct. Used by RandomCircle for its color field.
@@ -68,7 +186,7 @@ where
 // RandomCircle definition for wasm.
 #[cfg(target_arch = "wasm32")]
 #[wasm_bindgen]
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct RandomCircle {
     pub imgx: u32,
     pub imgy: u32,
@@ -88,7 +206,7 @@ pub struct RandomCircle {
 
 // RandomCircle definition not for wasm.
 #[cfg(not(target_arch = "wasm32"))]
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct RandomCircle {
     pub imgx: u32,
     pub imgy: u32,
@@ -168,8 +286,8 @@ impl RandomShape for RandomCircle {
         &self,
         target_img: &image::RgbaImage,
         current_img: &image::RgbaImage,
-    ) -> i128 {
-        if self.get_bounds() == None {
+    ) -> i64 {
+        if self.get_bounds().is_none() {
             return 0; // If the bounds lay outside the image, this shape does not change the image
         }
         self.score_bresenham(target_img, current_img)
@@ -197,28 +315,49 @@ impl RandomShape for RandomCircle {
             color: self.color,
         }
     }
+
+    fn render(&self, image: &mut image::RgbaImage, scale: f64) {
+        // draw_filled_circle fills the pixels whose centers lie within the radius of the center
+        // pixel, which is roughly a circle half a pixel larger around the center pixel's middle.
+        let center = (
+            (f64::from(self.center.0) + 0.5) * scale,
+            (f64::from(self.center.1) + 0.5) * scale,
+        );
+        let radius = (f64::from(self.radius) + 0.5) * scale;
+        fill_circle_antialiased(image, center, radius, self.color);
+    }
 }
 
 impl RandomCircle {
     #[must_use]
     pub fn new(imgx: u32, imgy: u32) -> Self {
+        Self::new_with_options(imgx, imgy, &ShapeOptions::default())
+    }
+
+    #[must_use]
+    pub fn new_with_options(imgx: u32, imgy: u32, options: &ShapeOptions) -> Self {
+        Self::new_with_rng(imgx, imgy, options, &mut rand::thread_rng())
+    }
+
+    // Like new_with_options, but draws all random values from the given generator, so that
+    // runs can be reproduced from a seed.
+    pub fn new_with_rng<R: Rng + ?Sized>(
+        imgx: u32,
+        imgy: u32,
+        options: &ShapeOptions,
+        rng: &mut R,
+    ) -> Self {
         let simgx = imgx as i32;
         let simgy = imgy as i32;
 
-        let mut rng = rand::thread_rng();
-        let max_radius = cmp::max(simgx, simgy);
+        let (min_radius, max_radius) = options.radius_range(imgx, imgy);
 
         Self {
             imgx,
             imgy,
             center: (rng.gen_range(0..simgx), rng.gen_range(0..simgy)),
-            radius: rng.gen_range(1..max_radius),
-            color: image::Rgba([
-                rng.gen_range(0..=255),
-                rng.gen_range(0..=255),
-                rng.gen_range(0..=255),
-                255,
-            ]),
+            radius: rng.gen_range(min_radius..=max_radius),
+            color: options.color_mode.random_color(rng),
         }
     }
 
@@ -230,8 +369,8 @@ impl RandomCircle {
         &self,
         target_img: &image::RgbaImage,
         current_img: &image::RgbaImage,
-        prev_score: u128,
-    ) -> u128 {
+        prev_score: Score,
+    ) -> Score {
         let bounds = self.get_bounds().unwrap();
 
         let cropped_target = target_img
@@ -246,7 +385,7 @@ impl RandomCircle {
         let prev_cropped_score = image_diff(&cropped_target, &cropped_current);
         let new_cropped_score = image_diff(&cropped_target, &new_img);
 
-        prev_score + new_cropped_score - prev_cropped_score
+        Score(prev_score.0 + new_cropped_score.0 - prev_cropped_score.0)
     }
 
     // On shapes with large bounding boxes, it's best to avoid cropping and simply draw and score
@@ -256,17 +395,11 @@ impl RandomCircle {
         &self,
         target_img: &image::RgbaImage,
         current_img: &image::RgbaImage,
-    ) -> u128 {
+    ) -> Score {
         let new_img = self.draw(current_img);
         image_diff(target_img, &new_img)
     }
 
-    fn pixel_diff(p1: &[u8], p2: &[u8]) -> i128 {
-        (p1[0].abs_diff(p2[0])) as i128
-            + (p1[1].abs_diff(p2[1])) as i128
-            + (p1[2].abs_diff(p2[2])) as i128
-    }
-
     // Calculates the score difference after drawing a horizontal line across current_img.
     fn score_diff_for_line_horizontal(
         target_img: &image::RgbaImage,
@@ -275,8 +408,8 @@ impl RandomCircle {
         x1: i32,
         y: i32,
         color: Rgba<u8>,
-    ) -> i128 {
-        let mut diff: i128 = 0;
+    ) -> i64 {
+        let mut diff: i64 = 0;
 
         let (width, height): (u32, u32) = target_img.dimensions();
 
@@ -295,18 +428,15 @@ impl RandomCircle {
             // Convert y to a u32
             let y: u32 = y.try_into().unwrap();
 
-            // Loop over every pixel along the line and calculate the potential difference in score
-            // of applying the color to that pixel
-            for x in x0..x1 {
-                // x and y have already been bounds-checked, so we can index directly into
-                // the underlying pixel buffer without worry.
-                let index: usize = 4 * usize::try_from(x + width * y).unwrap();
-                let target_pixel = &target_img.as_raw()[index..index + 4];
-                let current_pixel = &current_img.as_raw()[index..index + 4];
-
-                diff += Self::pixel_diff(target_pixel, color.channels())
-                    - Self::pixel_diff(target_pixel, current_pixel);
-            }
+            // x and y have already been bounds-checked, so we can index directly into the
+            // underlying pixel buffers without worry.
+            let start = 4 * usize::try_from(x0 + width * y).unwrap();
+            let end = 4 * usize::try_from(x1 + width * y).unwrap();
+            diff = span_diff(
+                &target_img.as_raw()[start..end],
+                &current_img.as_raw()[start..end],
+                color,
+            );
         }
 
         //let line_iterator = BresenhamLineIter::new((x0 as f32, y as f32), (x1 as f32, y as f32));
@@ -318,8 +448,8 @@ impl RandomCircle {
         &self,
         target_img: &image::RgbaImage,
         current_img: &image::RgbaImage,
-    ) -> i128 {
-        let mut diff: i128 = 0;
+    ) -> i64 {
+        let mut diff: i64 = 0;
 
         let mut error = -self.radius;
         let mut x = self.radius;
@@ -371,8 +501,8 @@ impl RandomCircle {
         x: i32,
         y: i32,
         color: image::Rgba<u8>,
-    ) -> i128 {
-        let mut diff: i128 = 0;
+    ) -> i64 {
+        let mut diff: i64 = 0;
         diff += Self::score_diff_for_line_horizontal(
             target_img,
             current_img,
@@ -398,15 +528,23 @@ impl RandomCircle {
 #[cfg(test)]
 mod tests {
     use crate::image_diff::image_diff;
-    use crate::random_shape::{BoundingBox, RandomCircle, RandomShape};
+    use crate::random_shape::{
+        BoundingBox, RandomCircle, RandomShape, ShapeOptions, SizeSchedule,
+    };
+    use crate::score::Score;
     use image::RgbaImage;
     use std::iter;
 
+    // Scores are weighted by the target's alpha, so targets used for testing must be opaque.
+    fn opaque_black(imgx: u32, imgy: u32) -> RgbaImage {
+        RgbaImage::from_fn(imgx, imgy, |_x, _y| image::Rgba([0, 0, 0, 255]))
+    }
+
     fn assert_scoring_equal(
         shape: &RandomCircle,
         target_img: &image::RgbaImage,
         current_img: &image::RgbaImage,
-        prev_score: u128,
+        prev_score: Score,
     ) {
         match shape.get_bounds() {
             Some(_b) => {}
@@ -420,7 +558,7 @@ mod tests {
         // The Bresenham algorithm isn't exactly the same as the others - we're happy with it being
         // within a 10% margin.
         assert!(
-            (i128::try_from(score_small).unwrap() - score_bresenham).abs() as f64 / (score_small as f64) < 0.10,
+            (i64::try_from(score_small.0).unwrap() - score_bresenham).abs() as f64 / (score_small.0 as f64) < 0.10,
             "{} !~= {}",
             score_small,
             score_bresenham
@@ -434,7 +572,7 @@ mod tests {
         // Create 1000 random shapes for testing
         let shapes = iter::repeat_with(|| RandomCircle::new(imgx, imgy)).take(1000);
 
-        let target_img = RgbaImage::new(imgx, imgy);
+        let target_img = opaque_black(imgx, imgy);
         let current_img = RgbaImage::new(imgx, imgy);
         let prev_score = image_diff(&target_img, &current_img);
 
@@ -456,7 +594,7 @@ mod tests {
         })
         .take(1000);
 
-        let target_img = RgbaImage::new(imgx, imgy);
+        let target_img = opaque_black(imgx, imgy);
         let current_img = RgbaImage::new(imgx, imgy);
         let prev_score = image_diff(&target_img, &current_img);
 
@@ -469,7 +607,7 @@ mod tests {
     fn test_score_algs_equal_shape_outside_canvas() {
         let (imgx, imgy) = (50, 75);
 
-        let target_img = RgbaImage::new(imgx, imgy);
+        let target_img = opaque_black(imgx, imgy);
         let current_img = RgbaImage::new(imgx, imgy);
         let prev_score = image_diff(&target_img, &current_img);
 
@@ -505,11 +643,25 @@ mod tests {
         assert_eq!(shape.get_bounds(), Some(expected_bounds));
     }
 
+    #[test]
+    fn test_bounds_intersect() {
+        let bounds = |x, y, width, height| BoundingBox {
+            x,
+            y,
+            width,
+            height,
+        };
+        assert!(bounds(0, 0, 10, 10).intersects(&bounds(5, 5, 10, 10)));
+        assert!(bounds(5, 5, 1, 1).intersects(&bounds(0, 0, 10, 10)));
+        assert!(!bounds(0, 0, 10, 10).intersects(&bounds(10, 0, 10, 10)));
+        assert!(!bounds(0, 0, 10, 10).intersects(&bounds(0, 10, 10, 10)));
+    }
+
     #[test]
     fn test_score_small_shape_fills_canvas() {
         let (imgx, imgy) = (50, 75);
 
-        let target_img = RgbaImage::new(imgx, imgy);
+        let target_img = opaque_black(imgx, imgy);
         let current_img = RgbaImage::new(imgx, imgy);
         let prev_score = image_diff(&target_img, &current_img);
 
@@ -522,7 +674,7 @@ mod tests {
         };
         assert_eq!(
             shape.score_small(&target_img, &current_img, prev_score),
-            (imgx * imgy * 255 * 3) as u128
+            Score(u64::from(imgx * imgy * 255 * 3))
         );
     }
 
@@ -530,11 +682,11 @@ mod tests {
     fn test_score_large_shape_fills_canvas() {
         let (imgx, imgy) = (50, 75);
 
-        let target_img = RgbaImage::new(imgx, imgy);
+        let target_img = opaque_black(imgx, imgy);
         let current_img = RgbaImage::new(imgx, imgy);
         let prev_score = image_diff(&target_img, &current_img);
 
-        assert_eq!(prev_score, 0);
+        assert_eq!(prev_score, Score(0));
 
         let shape = RandomCircle {
             imgx,
@@ -545,7 +697,7 @@ mod tests {
         };
         assert_eq!(
             shape.score_large(&target_img, &current_img),
-            (imgx * imgy * 255 * 3) as u128
+            Score(u64::from(imgx * imgy * 255 * 3))
         );
     }
 
@@ -553,7 +705,7 @@ mod tests {
     fn test_score_algs_equal_shape_fills_canvas() {
         let (imgx, imgy) = (50, 75);
 
-        let target_img = RgbaImage::new(imgx, imgy);
+        let target_img = opaque_black(imgx, imgy);
         let current_img = RgbaImage::new(imgx, imgy);
         let prev_score = image_diff(&target_img, &current_img);
 
@@ -566,4 +718,91 @@ mod tests {
         };
         assert_scoring_equal(&shape, &target_img, &current_img, prev_score);
     }
+
+    #[test]
+    fn test_score_transparent_target() {
+        let (imgx, imgy) = (50, 75);
+
+        let target_img = RgbaImage::from_fn(imgx, imgy, |x, _y| {
+            if x < 25 {
+                image::Rgba([255, 255, 255, 0])
+            } else {
+                image::Rgba([255, 255, 255, 255])
+            }
+        });
+        let current_img = RgbaImage::new(imgx, imgy);
+
+        // A circle drawn entirely over the transparent half of the target changes nothing.
+        let shape = RandomCircle {
+            imgx,
+            imgy,
+            center: (10, 30),
+            radius: 5,
+            color: image::Rgba([0, 255, 0, 255]),
+        };
+        assert_eq!(shape.score(&target_img, &current_img), 0);
+
+        let shape = RandomCircle {
+            center: (40, 30),
+            ..shape
+        };
+        assert!(shape.score(&target_img, &current_img) < 0);
+    }
+
+    #[test]
+    fn test_new_shapes_respect_radius_range() {
+        let options = ShapeOptions {
+            min_radius: 3,
+            max_radius: Some(7),
+            ..ShapeOptions::default()
+        };
+
+        let shapes = iter::repeat_with(|| RandomCircle::new_with_options(50, 75, &options));
+        for shape in shapes.take(1000) {
+            assert!((3..=7).contains(&shape.radius), "radius {} out of range", shape.radius);
+        }
+    }
+
+    #[test]
+    fn test_default_radius_range() {
+        assert_eq!(ShapeOptions::default().radius_range(50, 75), (1, 74));
+    }
+
+    #[test]
+    fn test_linear_size_schedule() {
+        let schedule = SizeSchedule::Linear {
+            final_max_radius: 10,
+            num_shapes: 100,
+        };
+        assert_eq!(schedule.max_radius(110, 0), 110);
+        assert_eq!(schedule.max_radius(110, 50), 60);
+        assert_eq!(schedule.max_radius(110, 100), 10);
+        assert_eq!(schedule.max_radius(110, 1000), 10);
+    }
+
+    #[test]
+    fn test_exponential_size_schedule() {
+        let schedule = SizeSchedule::Exponential {
+            final_max_radius: 10,
+            half_life: 20,
+        };
+        assert_eq!(schedule.max_radius(110, 0), 110);
+        assert_eq!(schedule.max_radius(110, 20), 60);
+        assert_eq!(schedule.max_radius(110, 40), 35);
+    }
+
+    #[test]
+    fn test_options_for_shape_count() {
+        let options = ShapeOptions {
+            min_radius: 5,
+            size_schedule: SizeSchedule::Linear {
+                final_max_radius: 0,
+                num_shapes: 10,
+            },
+            ..ShapeOptions::default()
+        };
+        let options = options.for_shape_count(50, 75, 100);
+        assert_eq!(options.radius_range(50, 75), (5, 5));
+        assert_eq!(options.size_schedule, SizeSchedule::Constant);
+    }
 }
@@ -0,0 +1,138 @@
+use crate::random_shape::{RandomCircle, RandomShape};
+use image::{Rgba, RgbaImage};
+
+// Fills a circle with anti-aliased edges. Coordinates are continuous, so the pixel at (x, y)
+// covers the square from (x, y) to (x + 1, y + 1). Each pixel is blended with the color by the
+// fraction of it which the circle covers, approximated from the distance between the pixel
+// center and the edge.
+pub fn fill_circle_antialiased(img: &mut RgbaImage, center: (f64, f64), radius: f64, color: Rgba<u8>) {
+    let (width, height) = img.dimensions();
+    let (cx, cy) = center;
+    let x_min = (cx - radius - 1.0).floor().max(0.0) as u32;
+    let y_min = (cy - radius - 1.0).floor().max(0.0) as u32;
+    let x_max = ((cx + radius + 1.0).ceil().max(0.0) as u32).min(width);
+    let y_max = ((cy + radius + 1.0).ceil().max(0.0) as u32).min(height);
+
+    for y in y_min..y_max {
+        for x in x_min..x_max {
+            let dx = f64::from(x) + 0.5 - cx;
+            let dy = f64::from(y) + 0.5 - cy;
+            let coverage = (radius + 0.5 - (dx * dx + dy * dy).sqrt()).clamp(0.0, 1.0);
+            if coverage > 0.0 {
+                blend(img.get_pixel_mut(x, y), color, coverage);
+            }
+        }
+    }
+}
+
+// Draws color over dst, with the color's alpha scaled by coverage.
+fn blend(dst: &mut Rgba<u8>, color: Rgba<u8>, coverage: f64) {
+    let src_alpha = coverage * f64::from(color[3]) / 255.0;
+    let dst_alpha = f64::from(dst[3]) / 255.0;
+    let out_alpha = src_alpha + dst_alpha * (1.0 - src_alpha);
+    if out_alpha <= 0.0 {
+        return;
+    }
+
+    for c in 0..3 {
+        let value = (f64::from(color[c]) * src_alpha
+            + f64::from(dst[c]) * dst_alpha * (1.0 - src_alpha))
+            / out_alpha;
+        dst[c] = value.round() as u8;
+    }
+    dst[3] = (out_alpha * 255.0).round() as u8;
+}
+
+// Renders the shapes, evolved on an image of the given size, onto a transparent image `scale`
+// times as large.
+#[must_use]
+pub fn render(shapes: &[RandomCircle], width: u32, height: u32, scale: f64) -> RgbaImage {
+    let mut img = RgbaImage::new(
+        (f64::from(width) * scale).round() as u32,
+        (f64::from(height) * scale).round() as u32,
+    );
+    for shape in shapes {
+        shape.render(&mut img, scale);
+    }
+    img
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::random_shape::{RandomCircle, RandomShape};
+    use crate::render::{fill_circle_antialiased, render};
+    use image::{Rgba, RgbaImage};
+
+    fn white_on_black(size: u32, center: (f64, f64), radius: f64) -> RgbaImage {
+        let mut img = RgbaImage::from_pixel(size, size, Rgba([0, 0, 0, 255]));
+        fill_circle_antialiased(&mut img, center, radius, Rgba([255, 255, 255, 255]));
+        img
+    }
+
+    #[test]
+    fn test_edges_are_blended() {
+        let img = white_on_black(20, (10.5, 10.5), 5.0);
+
+        assert_eq!(img.get_pixel(10, 10), &Rgba([255, 255, 255, 255]));
+        assert_eq!(img.get_pixel(0, 0), &Rgba([0, 0, 0, 255]));
+        // The edge of the circle runs through the middle of this pixel.
+        let edge = img.get_pixel(15, 10)[0];
+        assert!(edge > 64 && edge < 192, "edge pixel is {}", edge);
+        // Blending never makes the image translucent.
+        assert!(img.pixels().all(|p| p[3] == 255));
+    }
+
+    #[test]
+    fn test_coverage_matches_area() {
+        // The total coverage should be close to the area of the circle.
+        let radius = 7.3;
+        let img = white_on_black(30, (14.6, 15.2), radius);
+        let covered: f64 = img.pixels().map(|p| f64::from(p[0]) / 255.0).sum();
+        let area = std::f64::consts::PI * radius * radius;
+        assert!((covered - area).abs() / area < 0.01, "{} vs {}", covered, area);
+    }
+
+    #[test]
+    fn test_circle_outside_image() {
+        let img = white_on_black(10, (-20.0, 30.0), 5.0);
+        assert!(img.pixels().all(|p| p == &Rgba([0, 0, 0, 255])));
+    }
+
+    #[test]
+    fn test_render_matches_aliased_draw() {
+        let shapes = vec![
+            RandomCircle {
+                imgx: 40,
+                imgy: 30,
+                center: (12, 14),
+                radius: 8,
+                color: Rgba([200, 30, 30, 255]),
+            },
+            RandomCircle {
+                imgx: 40,
+                imgy: 30,
+                center: (25, 10),
+                radius: 6,
+                color: Rgba([20, 30, 230, 255]),
+            },
+        ];
+
+        let mut aliased = RgbaImage::new(40, 30);
+        for shape in &shapes {
+            aliased = shape.draw(&aliased);
+        }
+        let rendered = render(&shapes, 40, 30, 1.0);
+
+        // Only pixels along the edges may differ.
+        let different = aliased
+            .pixels()
+            .zip(rendered.pixels())
+            .filter(|(a, r)| a != r)
+            .count();
+        assert!(different < 150, "{} pixels differ", different);
+
+        let upscaled = render(&shapes, 40, 30, 4.0);
+        assert_eq!(upscaled.dimensions(), (160, 120));
+        assert_eq!(upscaled.get_pixel(12 * 4 + 2, 14 * 4 + 2), &Rgba([200, 30, 30, 255]));
+    }
+}
@@ -0,0 +1,76 @@
+use image::imageops::{self, FilterType};
+use image::RgbaImage;
+use serde::{Deserialize, Serialize};
+
+// The filter used to scale a target image to the size shapes are evolved at.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ResizeFilter {
+    // Each pixel becomes the average of the pixels it covers. Fine textures turn into their
+    // average color, instead of into noise that shapes would try to reproduce.
+    #[default]
+    Area,
+    Nearest,
+    Triangle,
+    CatmullRom,
+    Gaussian,
+    Lanczos3,
+}
+
+impl ResizeFilter {
+    #[must_use]
+    pub fn resize(self, img: &RgbaImage, width: u32, height: u32) -> RgbaImage {
+        if img.dimensions() == (width, height) {
+            return img.clone();
+        }
+        let filter = match self {
+            Self::Area => return imageops::thumbnail(img, width, height),
+            Self::Nearest => FilterType::Nearest,
+            Self::Triangle => FilterType::Triangle,
+            Self::CatmullRom => FilterType::CatmullRom,
+            Self::Gaussian => FilterType::Gaussian,
+            Self::Lanczos3 => FilterType::Lanczos3,
+        };
+        imageops::resize(img, width, height, filter)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::resize::ResizeFilter;
+    use image::{Rgba, RgbaImage};
+
+    fn stripes(width: u32, height: u32) -> RgbaImage {
+        RgbaImage::from_fn(width, height, |x, _y| {
+            if x % 2 == 0 {
+                Rgba([0, 0, 0, 255])
+            } else {
+                Rgba([240, 240, 240, 255])
+            }
+        })
+    }
+
+    #[test]
+    fn test_area_averages_stripes() {
+        let img = ResizeFilter::Area.resize(&stripes(40, 10), 20, 5);
+        assert!(img.pixels().all(|p| *p == Rgba([120, 120, 120, 255])));
+
+        // Nearest neighbour picks a single column of each pair.
+        let img = ResizeFilter::Nearest.resize(&stripes(40, 10), 20, 5);
+        assert!(img.pixels().all(|p| p[0] == 0 || p[0] == 240));
+    }
+
+    #[test]
+    fn test_same_size_is_unchanged() {
+        let img = stripes(12, 7);
+        for filter in [ResizeFilter::Area, ResizeFilter::Triangle, ResizeFilter::Lanczos3] {
+            assert_eq!(filter.resize(&img, 12, 7), img);
+        }
+    }
+
+    #[test]
+    fn test_serialized_names() {
+        assert_eq!(serde_json::to_string(&ResizeFilter::Area).unwrap(), "\"area\"");
+        assert_eq!(serde_json::to_string(&ResizeFilter::CatmullRom).unwrap(), "\"catmullrom\"");
+    }
+}
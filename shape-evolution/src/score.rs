@@ -0,0 +1,90 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+// The total difference between a target and an image, as returned by image_diff. Lower is
+// better.
+//
+// Each pixel contributes at most 3 * 255 (see pixel_diff), so a u64 holds the score of any image
+// with up to 2^54 pixels. The change in score caused by drawing a shape is an i64, which holds the
+// change for any image with up to 2^53 pixels. Both are far more than fits in memory, and unlike
+// 128-bit integers, 64-bit ones are native on wasm32.
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(transparent)]
+pub struct Score(pub u64);
+
+impl Score {
+    #[must_use]
+    pub fn per_pixel(self, num_pixels: u32) -> f64 {
+        self.0 as f64 / f64::from(num_pixels)
+    }
+
+    // Applies a change in score, as returned by RandomShape::score. Circles are scored with an
+    // approximation of the pixels they cover (see score_bresenham), so a running score is
+    // expected to drift from the true difference. The drift comes only from pixels on the edge
+    // of each circle, so it stays small next to the score, and image_diff gives the exact value
+    // wherever it matters. Near a perfect match the drift could take the score below zero, so it
+    // stops at zero instead of failing.
+    #[must_use]
+    pub fn apply(self, delta: i64) -> Self {
+        Self(self.0.saturating_add_signed(delta))
+    }
+}
+
+impl fmt::Display for Score {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::image_diff::image_diff;
+    use crate::score::Score;
+    use crate::span::span_diff;
+    use image::{Rgba, RgbaImage};
+
+    // An all-black image against an all-white target is as far apart as images get. Its score is
+    // counted exactly, and covering every row with white brings it back to exactly zero.
+    #[test]
+    fn test_worst_case_is_exact() {
+        let (imgx, imgy) = (2000, 1000);
+        let white = Rgba([255, 255, 255, 255]);
+        let target = RgbaImage::from_pixel(imgx, imgy, white);
+        let current = RgbaImage::from_pixel(imgx, imgy, Rgba([0, 0, 0, 255]));
+
+        let score = image_diff(&target, &current);
+        assert_eq!(score, Score(3 * 255 * u64::from(imgx * imgy)));
+
+        let row_len = imgx as usize * 4;
+        let rows = target.as_raw().chunks(row_len).zip(current.as_raw().chunks(row_len));
+        let covered = rows.fold(score, |score, (t, c)| score.apply(span_diff(t, c, white)));
+        assert_eq!(covered, Score(0));
+
+        // The same at the largest supported sizes: 2^54 pixels for a score and 2^53 for a change.
+        let max_score = Score((3 * 255) << 54);
+        let max_change = -((3 * 255_i64) << 53);
+        assert_eq!(max_score.apply(max_change).apply(max_change), Score(0));
+    }
+
+    #[test]
+    fn test_apply() {
+        assert_eq!(Score(100).apply(-30), Score(70));
+        assert_eq!(Score(100).apply(30), Score(130));
+        assert_eq!(Score(100).apply(-100), Score(0));
+    }
+
+    #[test]
+    fn test_apply_below_zero() {
+        assert_eq!(Score(100).apply(-101), Score(0));
+        assert_eq!(Score(0).apply(i64::MIN), Score(0));
+    }
+
+    #[test]
+    fn test_serializes_as_number() {
+        assert_eq!(serde_json::to_string(&Score(1234)).unwrap(), "1234");
+        assert_eq!(Score(1234).to_string(), "1234");
+        assert_eq!(Score(1000).per_pixel(400), 2.5);
+    }
+}
@@ -0,0 +1,186 @@
+// Scoring of horizontal spans of pixels, the hot path of RandomCircle::score. On x86_64 (SSE2)
+// and on wasm32 built with the simd128 target feature, four pixels are processed per step. The
+// vectorized versions give exactly the same result as the scalar loop.
+
+use crate::image_diff::pixel_diff;
+use image::Rgba;
+
+// Returns how much the difference to the target changes when all pixels of `current` are
+// replaced by `color`. Both slices hold the same span of RGBA pixels.
+#[must_use]
+pub fn span_diff(target: &[u8], current: &[u8], color: Rgba<u8>) -> i64 {
+    assert_eq!(target.len(), current.len());
+
+    #[cfg(target_arch = "x86_64")]
+    let diff = sse2::span_diff(target, current, color);
+    #[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+    let diff = simd128::span_diff(target, current, color);
+    #[cfg(not(any(
+        target_arch = "x86_64",
+        all(target_arch = "wasm32", target_feature = "simd128")
+    )))]
+    let diff = span_diff_scalar(target, current, color);
+
+    diff
+}
+
+#[must_use]
+pub fn span_diff_scalar(target: &[u8], current: &[u8], color: Rgba<u8>) -> i64 {
+    target
+        .chunks_exact(4)
+        .zip(current.chunks_exact(4))
+        .map(|(t, c)| i64::from(pixel_diff(t, &color.0)) - i64::from(pixel_diff(t, c)))
+        .sum()
+}
+
+// The vectorized versions compute pixel_diff in 32-bit lanes, one pixel per lane. The product of
+// the channel difference (at most 3 * 255) and the alpha fits in 18 bits, and each lane of the
+// accumulator grows by at most 3 * 255 per step, so lanes cannot overflow for any realistic span.
+//
+// Division by 255 uses ((y << 8) + y + (y >> 8)) >> 16 with y = x + 1, which equals x / 255 for
+// every x up to 3 * 255 * 255.
+#[cfg(target_arch = "x86_64")]
+mod sse2 {
+    use super::span_diff_scalar;
+    use image::Rgba;
+    use std::arch::x86_64::*;
+
+    pub fn span_diff(target: &[u8], current: &[u8], color: Rgba<u8>) -> i64 {
+        let vector_len = target.len() / 16 * 16;
+        // SSE2 is part of the x86_64 baseline, and all loads stay within the slices.
+        let lanes = unsafe {
+            let color_vec = _mm_set1_epi32(i32::from_le_bytes(color.0));
+            let mut acc = _mm_setzero_si128();
+            for i in (0..vector_len).step_by(16) {
+                let t = _mm_loadu_si128(target.as_ptr().add(i).cast());
+                let c = _mm_loadu_si128(current.as_ptr().add(i).cast());
+                acc = _mm_add_epi32(acc, _mm_sub_epi32(pixel_diffs(t, color_vec), pixel_diffs(t, c)));
+            }
+            let mut lanes = [0i32; 4];
+            _mm_storeu_si128(lanes.as_mut_ptr().cast(), acc);
+            lanes
+        };
+
+        lanes.iter().map(|&lane| i64::from(lane)).sum::<i64>()
+            + span_diff_scalar(&target[vector_len..], &current[vector_len..], color)
+    }
+
+    unsafe fn pixel_diffs(target: __m128i, other: __m128i) -> __m128i {
+        let abs = _mm_or_si128(_mm_subs_epu8(target, other), _mm_subs_epu8(other, target));
+        let abs = _mm_and_si128(abs, _mm_set1_epi32(0x00ff_ffff));
+        let byte = _mm_set1_epi32(0xff);
+        let sum = _mm_add_epi32(
+            _mm_add_epi32(_mm_and_si128(abs, byte), _mm_and_si128(_mm_srli_epi32(abs, 8), byte)),
+            _mm_srli_epi32(abs, 16),
+        );
+        let alpha = _mm_srli_epi32(target, 24);
+        // Both factors fit in the low 16 bits of each lane, so this is a 32-bit multiply.
+        div255(_mm_madd_epi16(sum, alpha))
+    }
+
+    unsafe fn div255(x: __m128i) -> __m128i {
+        let y = _mm_add_epi32(x, _mm_set1_epi32(1));
+        let sum = _mm_add_epi32(_mm_add_epi32(_mm_slli_epi32(y, 8), y), _mm_srli_epi32(y, 8));
+        _mm_srli_epi32(sum, 16)
+    }
+}
+
+#[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+mod simd128 {
+    use super::span_diff_scalar;
+    use image::Rgba;
+    use std::arch::wasm32::*;
+
+    pub fn span_diff(target: &[u8], current: &[u8], color: Rgba<u8>) -> i64 {
+        let vector_len = target.len() / 16 * 16;
+        let color_vec = u32x4_splat(u32::from_le_bytes(color.0));
+        let mut acc = i32x4_splat(0);
+        for i in (0..vector_len).step_by(16) {
+            // All loads stay within the slices.
+            let (t, c) = unsafe {
+                (
+                    v128_load(target.as_ptr().add(i).cast()),
+                    v128_load(current.as_ptr().add(i).cast()),
+                )
+            };
+            acc = i32x4_add(acc, i32x4_sub(pixel_diffs(t, color_vec), pixel_diffs(t, c)));
+        }
+
+        let lanes = [
+            i32x4_extract_lane::<0>(acc),
+            i32x4_extract_lane::<1>(acc),
+            i32x4_extract_lane::<2>(acc),
+            i32x4_extract_lane::<3>(acc),
+        ];
+        lanes.iter().map(|&lane| i64::from(lane)).sum::<i64>()
+            + span_diff_scalar(&target[vector_len..], &current[vector_len..], color)
+    }
+
+    fn pixel_diffs(target: v128, other: v128) -> v128 {
+        let abs = v128_or(u8x16_sub_sat(target, other), u8x16_sub_sat(other, target));
+        let abs = v128_and(abs, u32x4_splat(0x00ff_ffff));
+        let byte = u32x4_splat(0xff);
+        let sum = i32x4_add(
+            i32x4_add(v128_and(abs, byte), v128_and(u32x4_shr(abs, 8), byte)),
+            u32x4_shr(abs, 16),
+        );
+        let alpha = u32x4_shr(target, 24);
+        div255(i32x4_mul(sum, alpha))
+    }
+
+    fn div255(x: v128) -> v128 {
+        let y = i32x4_add(x, i32x4_splat(1));
+        let sum = i32x4_add(i32x4_add(i32x4_shl(y, 8), y), u32x4_shr(y, 8));
+        u32x4_shr(sum, 16)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::span::{span_diff, span_diff_scalar};
+    use image::Rgba;
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha8Rng;
+
+    #[test]
+    fn test_div255_formula() {
+        for x in 0..=3 * 255 * 255u32 {
+            let y = x + 1;
+            assert_eq!(((y << 8) + y + (y >> 8)) >> 16, x / 255, "x = {}", x);
+        }
+    }
+
+    #[test]
+    fn test_span_diff() {
+        let target = [10, 20, 30, 255, 0, 0, 0, 0, 200, 100, 0, 51];
+        let current = [0, 0, 0, 255, 255, 255, 255, 255, 0, 0, 0, 255];
+        let color = Rgba([10, 20, 40, 255]);
+        // (10 - 60) + (0 - 0) + (310 * 51 / 255 - 300 * 51 / 255)
+        assert_eq!(span_diff(&target, &current, color), -48);
+        assert_eq!(span_diff_scalar(&target, &current, color), -48);
+    }
+
+    #[test]
+    fn test_vectorized_matches_scalar() {
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        for _ in 0..1000 {
+            let len = 4 * rng.gen_range(0..64);
+            let mut target = vec![0u8; len];
+            let mut current = vec![0u8; len];
+            rng.fill(&mut target[..]);
+            rng.fill(&mut current[..]);
+            // Extreme values are the most likely to break the vectorized arithmetic.
+            if rng.gen_bool(0.5) {
+                for byte in target.iter_mut().chain(current.iter_mut()) {
+                    *byte = if rng.gen_bool(0.5) { 0 } else { 255 };
+                }
+            }
+            let color = Rgba(rng.gen());
+
+            assert_eq!(
+                span_diff(&target, &current, color),
+                span_diff_scalar(&target, &current, color)
+            );
+        }
+    }
+}
@@ -0,0 +1,162 @@
+// End-to-end regression tests for the evolution pipeline. Small synthetic targets are evolved
+// with a fixed seed, and the results are checked against a score threshold and against the
+// renders stored in tests/golden. After an intended change to the output, regenerate the renders
+// with `UPDATE_GOLDEN=1 cargo test --test golden` and check them by eye.
+
+use image::{Rgba, RgbaImage};
+use shape_evolution::evolve::{evolve, EvolveOptions};
+use shape_evolution::output::{OutputFormat, OutputOptions, Snapshots};
+use shape_evolution::progress::Event;
+use shape_evolution::resize::ResizeFilter;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const SIZE: u32 = 48;
+
+fn options() -> EvolveOptions {
+    EvolveOptions {
+        num_epochs: 40,
+        num_gens: 10,
+        seed: Some(42),
+        output: OutputOptions {
+            format: OutputFormat::Png,
+            snapshots: Snapshots::Never,
+            ..OutputOptions::default()
+        },
+        ..EvolveOptions::default()
+    }
+}
+
+// Evolves the target and returns the final image along with its score per pixel.
+fn run(name: &str, target: &RgbaImage) -> (RgbaImage, f64) {
+    run_with_options(name, target, &options())
+}
+
+fn run_with_options(
+    name: &str,
+    target: &RgbaImage,
+    options: &EvolveOptions,
+) -> (RgbaImage, f64) {
+    let dir = std::env::temp_dir().join(format!("shape-evolution-golden-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let input_path = dir.join("target.png");
+    target.save(&input_path).unwrap();
+
+    let mut score_per_pixel = f64::INFINITY;
+    let mut observer = |event: &Event| {
+        if let Event::EpochFinished {
+            score_per_pixel: score,
+            ..
+        } = *event
+        {
+            score_per_pixel = score;
+        }
+    };
+    evolve(&input_path, &dir, options, &mut observer).unwrap();
+
+    let output = image::open(dir.join("out.png")).unwrap().to_rgba8();
+    fs::remove_dir_all(dir).unwrap();
+    (output, score_per_pixel)
+}
+
+fn golden_path(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(format!("{}.png", name))
+}
+
+// Compares the output with the stored render, allowing for tiny rounding differences.
+fn check_golden(name: &str, output: &RgbaImage) {
+    let path = golden_path(name);
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        output.save(&path).unwrap();
+        return;
+    }
+
+    let golden = image::open(&path)
+        .unwrap_or_else(|e| panic!("could not open {}: {}", path.display(), e))
+        .to_rgba8();
+    assert_eq!(output.dimensions(), golden.dimensions());
+
+    let total: u64 = output
+        .as_raw()
+        .iter()
+        .zip(golden.as_raw())
+        .map(|(&a, &b)| u64::from(a.abs_diff(b)))
+        .sum();
+    let mean = total as f64 / output.as_raw().len() as f64;
+    assert!(
+        mean < 0.5,
+        "{} differs from {} by {:.2} per channel",
+        name,
+        path.display(),
+        mean
+    );
+}
+
+#[test]
+fn test_gradient() {
+    let target = RgbaImage::from_fn(SIZE, SIZE, |x, y| {
+        Rgba([(x * 255 / SIZE) as u8, (y * 255 / SIZE) as u8, 128, 255])
+    });
+    let (output, score_per_pixel) = run("gradient", &target);
+    assert!(score_per_pixel < 42.0, "score per pixel {}", score_per_pixel);
+    check_golden("gradient", &output);
+}
+
+#[test]
+fn test_checkerboard() {
+    let target = RgbaImage::from_fn(SIZE, SIZE, |x, y| {
+        if (x / 8 + y / 8) % 2 == 0 {
+            Rgba([255, 255, 255, 255])
+        } else {
+            Rgba([0, 0, 0, 255])
+        }
+    });
+    let (output, score_per_pixel) = run("checkerboard", &target);
+    assert!(score_per_pixel < 180.0, "score per pixel {}", score_per_pixel);
+    check_golden("checkerboard", &output);
+}
+
+#[test]
+fn test_disc() {
+    let center = f64::from(SIZE) / 2.0;
+    let target = RgbaImage::from_fn(SIZE, SIZE, |x, y| {
+        let (dx, dy) = (f64::from(x) + 0.5 - center, f64::from(y) + 0.5 - center);
+        if dx * dx + dy * dy < 15.0 * 15.0 {
+            Rgba([200, 40, 40, 255])
+        } else {
+            Rgba([240, 240, 230, 255])
+        }
+    });
+    let (output, score_per_pixel) = run("disc", &target);
+    assert!(score_per_pixel < 38.0, "score per pixel {}", score_per_pixel);
+    check_golden("disc", &output);
+}
+
+// Scaled down with nearest neighbour, fine stripes turn into stripes of full contrast that no
+// circle can reproduce. Averaging them leaves a far easier target.
+#[test]
+fn test_resize_filter_on_stripes() {
+    let target = RgbaImage::from_fn(3 * SIZE, 3 * SIZE, |x, _y| {
+        if x % 2 == 0 {
+            Rgba([20, 20, 20, 255])
+        } else {
+            Rgba([230, 230, 230, 255])
+        }
+    });
+    let score_with = |resize_filter| {
+        let options = EvolveOptions {
+            scale_down: 3.0,
+            resize_filter,
+            ..options()
+        };
+        run_with_options("stripes", &target, &options).1
+    };
+
+    let nearest = score_with(ResizeFilter::Nearest);
+    let area = score_with(ResizeFilter::Area);
+    assert!(area < nearest / 2.0, "area {} !< nearest {} / 2", area, nearest);
+}
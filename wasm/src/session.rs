@@ -0,0 +1,928 @@
+use image::codecs::png::PngEncoder;
+use image::imageops;
+use image::{ColorType, ImageEncoder, RgbaImage};
+use js_sys::ArrayBuffer;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+use std::cmp;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::{Clamped, JsCast};
+use web_sys::{Blob, ImageData};
+
+use shape_evolution::decode::{decode_image_with_options, DecodeOptions};
+use shape_evolution::evolve::{epoch, EvolveOptions, OptionsError, Setting};
+use shape_evolution::image_diff::{error_heatmap, image_diff, region_diffs, RegionDiff};
+use shape_evolution::palette::ColorMode;
+use shape_evolution::random_shape::{RandomCircle, RandomShape, ShapeOptions, SizeSchedule};
+use shape_evolution::resize::ResizeFilter;
+use shape_evolution::score::Score;
+
+use crate::{utils, web};
+
+// Types for the generated TypeScript definitions. Shapes are passed to JavaScript as plain
+// objects rather than wasm-bindgen classes, so they can be posted between workers and stored.
+#[wasm_bindgen(typescript_custom_section)]
+const TS_TYPES: &'static str = r#"
+export interface SessionOptions {
+    workingPixels?: number;
+    resizeFilter?: "area" | "nearest" | "triangle" | "catmullrom" | "gaussian" | "lanczos3";
+    shape?: "circle";
+    metric?: "absolute";
+    seed?: number;
+    generationSize?: number;
+    numGens?: number;
+    shapesPerEpoch?: number;
+    colorMode?: "full" | "grayscale" | "palette";
+    paletteSize?: number;
+    minRadius?: number;
+    maxRadius?: number;
+    sizeSchedule?: "constant" | "linear" | "exponential";
+    finalMaxRadius?: number;
+    scheduleLength?: number;
+}
+
+export interface Circle {
+    imgx: number;
+    imgy: number;
+    center: [number, number];
+    radius: number;
+    color: [number, number, number, number];
+}
+
+export interface RegionDiff {
+    x: number;
+    y: number;
+    width: number;
+    height: number;
+    score: number;
+}
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "SessionOptions")]
+    pub type SessionOptionsObject;
+
+    #[wasm_bindgen(typescript_type = "Circle[]")]
+    pub type CircleArray;
+
+    #[wasm_bindgen(typescript_type = "RegionDiff[]")]
+    pub type RegionDiffArray;
+}
+
+// Circles are the only shape so far, but naming the shape and the metric lets embedders pin down
+// what they rely on, and lets other shapes and metrics be added without changing the API.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ShapeName {
+    Circle,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MetricName {
+    // The sum of absolute RGB differences, weighted by the alpha of the target (see image_diff).
+    Absolute,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorModeName {
+    Full,
+    Grayscale,
+    Palette,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SizeScheduleName {
+    Constant,
+    Linear,
+    Exponential,
+}
+
+// Settings of a session, as passed from JavaScript. Every field is optional there, and unknown
+// fields are rejected. Radii are measured in pixels of the scaled-down target image.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "camelCase")]
+pub struct SessionOptions {
+    // The target image is scaled to roughly this many pixels before evolving shapes against it.
+    pub working_pixels: u32,
+    pub resize_filter: ResizeFilter,
+    pub shape: ShapeName,
+    pub metric: MetricName,
+    // Seed for the random number generator. Sessions with the same seed, options and target
+    // produce the same shapes. A random seed is used if this is not set.
+    pub seed: Option<u64>,
+    pub generation_size: usize,
+    pub num_gens: u32,
+    pub shapes_per_epoch: usize,
+    pub color_mode: ColorModeName,
+    // Number of colors extracted from the target in the "palette" color mode.
+    pub palette_size: usize,
+    pub min_radius: u32,
+    pub max_radius: Option<u32>,
+    pub size_schedule: SizeScheduleName,
+    pub final_max_radius: u32,
+    // The number of shapes over which the linear schedule shrinks, or the half-life of the
+    // exponential schedule.
+    pub schedule_length: u32,
+}
+
+impl Default for SessionOptions {
+    fn default() -> Self {
+        Self {
+            working_pixels: 200 * 200,
+            resize_filter: ResizeFilter::default(),
+            shape: ShapeName::Circle,
+            metric: MetricName::Absolute,
+            seed: None,
+            generation_size: 100,
+            num_gens: 30,
+            shapes_per_epoch: 1,
+            color_mode: ColorModeName::Full,
+            palette_size: 16,
+            min_radius: 1,
+            max_radius: None,
+            size_schedule: SizeScheduleName::Constant,
+            final_max_radius: 1,
+            schedule_length: 500,
+        }
+    }
+}
+
+impl SessionOptions {
+    fn from_js(options: Option<SessionOptionsObject>) -> Result<Self, JsValue> {
+        let options = match options {
+            Some(options) if !options.is_undefined() && !options.is_null() => {
+                serde_wasm_bindgen::from_value(options.into()).map_err(|e| e.to_string())
+            }
+            _ => Ok(Self::default()),
+        };
+        options
+            .and_then(|options| options.validate().map(|()| options))
+            .map_err(|e| JsValue::from_str(&format!("Invalid session options: {}", e)))
+    }
+
+    // Checks the options that deserialize fine but cannot be used.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.working_pixels == 0 {
+            return Err("workingPixels must be at least 1".to_string());
+        }
+        let options = EvolveOptions {
+            num_gens: self.num_gens,
+            generation_size: self.generation_size,
+            shapes_per_epoch: self.shapes_per_epoch,
+            shape: self.unresolved_shape_options(),
+            ..EvolveOptions::default()
+        };
+        // The same checks as on the command line, reported with the names used in JavaScript.
+        options.validate().map_err(|e| match e {
+            OptionsError::Zero(setting) => format!("{} must be at least 1", js_name(setting)),
+            OptionsError::NotPositive(setting) => {
+                format!("{} must be a number greater than 0", js_name(setting))
+            }
+            OptionsError::RadiusRange {
+                min_radius,
+                max_radius,
+            } => format!("maxRadius ({}) is smaller than minRadius ({})", max_radius, min_radius),
+        })
+    }
+
+    // Images are rejected beyond these sizes, which keeps decoding well within the memory
+    // available to WebAssembly. JPEGs are decoded at a reduced size instead where possible, and
+    // never at much more than the working size.
+    fn decode_options(&self) -> DecodeOptions {
+        const MAX_PIXELS: u64 = 25_000_000;
+        const MAX_ALLOC: u64 = 256 * 1024 * 1024;
+        DecodeOptions {
+            max_pixels: Some(MAX_PIXELS),
+            max_alloc: Some(MAX_ALLOC),
+            min_pixels: Some(u64::from(self.working_pixels)),
+        }
+    }
+
+    fn rng(&self) -> ChaCha8Rng {
+        match self.seed {
+            Some(seed) => ChaCha8Rng::seed_from_u64(seed),
+            None => ChaCha8Rng::from_entropy(),
+        }
+    }
+
+    // The shape options for a target image, with the palette extracted from it if requested.
+    #[must_use]
+    pub fn shape_options(&self, target_img: &RgbaImage) -> ShapeOptions {
+        let options = self.unresolved_shape_options();
+        ShapeOptions {
+            color_mode: options.color_mode.resolve(target_img),
+            ..options
+        }
+    }
+
+    fn unresolved_shape_options(&self) -> ShapeOptions {
+        let color_mode = match self.color_mode {
+            ColorModeName::Full => ColorMode::Full,
+            ColorModeName::Grayscale => ColorMode::Grayscale,
+            ColorModeName::Palette => ColorMode::Extracted(self.palette_size),
+        };
+        let size_schedule = match self.size_schedule {
+            SizeScheduleName::Constant => SizeSchedule::Constant,
+            SizeScheduleName::Linear => SizeSchedule::Linear {
+                final_max_radius: self.final_max_radius,
+                num_shapes: self.schedule_length,
+            },
+            SizeScheduleName::Exponential => SizeSchedule::Exponential {
+                final_max_radius: self.final_max_radius,
+                half_life: self.schedule_length,
+            },
+        };
+        ShapeOptions {
+            color_mode,
+            min_radius: self.min_radius,
+            max_radius: self.max_radius,
+            size_schedule,
+        }
+    }
+}
+
+// The name of a setting in SessionOptions.
+fn js_name(setting: Setting) -> &'static str {
+    match setting {
+        Setting::NumGens => "numGens",
+        Setting::GenerationSize => "generationSize",
+        Setting::ShapesPerEpoch => "shapesPerEpoch",
+        // Not a session option, since sessions are sized with workingPixels. It keeps its
+        // default, which is valid.
+        Setting::ScaleDown => "scaleDown",
+        Setting::PaletteSize => "paletteSize",
+        Setting::ScheduleLength => "scheduleLength",
+    }
+}
+
+// Exported session state starts with these bytes, followed by the version of the format.
+const STATE_MAGIC: &[u8] = b"SHEV";
+const STATE_VERSION: u8 = 2;
+
+// Imported states with more shapes are rejected, which bounds the time taken to redraw them. This
+// is far more than a session accepts in practice.
+const MAX_STATE_SHAPES: usize = 100_000;
+
+// Everything needed to resume a session exactly where it was exported. The current image is
+// redrawn and scored from the shapes, and the target is stored as a PNG, which is much smaller
+// than its pixels.
+#[derive(Serialize, Deserialize)]
+struct SessionState {
+    options: SessionOptions,
+    target_png: Vec<u8>,
+    shapes: Vec<RandomCircle>,
+    rng: ChaCha8Rng,
+}
+
+// An evolution run against a single target image. From JavaScript, create one with
+// `new Session(buffer, options)` from encoded image bytes, or with one of the static `from`
+// methods, and release it with `free()` once it is no longer needed.
+//
+// All of them work on the main thread as well as in workers.
+#[wasm_bindgen]
+pub struct Session {
+    target_img: RgbaImage,
+    current_img: RgbaImage,
+    score: Score,
+    options: SessionOptions,
+    shape_options: ShapeOptions,
+    shapes: Vec<RandomCircle>,
+    rng: ChaCha8Rng,
+}
+
+impl Session {
+    #[must_use]
+    pub fn from_image(target_img: RgbaImage, options: SessionOptions) -> Self {
+        let (width, height) = target_img.dimensions();
+
+        // Scale the target image to roughly the working number of pixels.
+        let target_scale_factor: f64 =
+            (f64::from(width * height) / f64::from(options.working_pixels)).sqrt();
+        let target_img = options.resize_filter.resize(
+            &target_img,
+            cmp::max((f64::from(width) / target_scale_factor) as u32, 1),
+            cmp::max((f64::from(height) / target_scale_factor) as u32, 1),
+        );
+        let (width, height) = target_img.dimensions();
+        let current_img = RgbaImage::new(width, height);
+
+        Self {
+            score: image_diff(&target_img, &current_img),
+            shape_options: options.shape_options(&target_img),
+            target_img,
+            current_img,
+            shapes: Vec::new(),
+            rng: options.rng(),
+            options,
+        }
+    }
+
+    // Runs a single epoch and returns the shapes it accepted, which are already drawn.
+    pub fn step_shapes(&mut self) -> Vec<RandomCircle> {
+        let (width, height) = self.target_img.dimensions();
+        let num_shapes = u32::try_from(self.shapes.len()).unwrap_or(u32::MAX);
+        let Some((best_shapes, new_score)) = epoch(
+            self.options.generation_size,
+            self.options.num_gens,
+            self.options.shapes_per_epoch,
+            &self.target_img,
+            &self.current_img,
+            self.score,
+            &self.shape_options.for_shape_count(width, height, num_shapes),
+            &mut self.rng,
+        ) else {
+            return Vec::new();
+        };
+
+        self.score = new_score;
+        for best_shape in &best_shapes {
+            self.current_img = best_shape.draw(&self.current_img);
+        }
+        self.shapes.extend_from_slice(&best_shapes);
+        best_shapes
+    }
+
+    // Runs epochs until `now()` reaches `deadline`, and returns the shapes they accepted.
+    pub fn run_until(&mut self, deadline: f64, now: impl Fn() -> f64) -> Vec<RandomCircle> {
+        let mut shapes = Vec::new();
+        loop {
+            shapes.extend(self.step_shapes());
+            if now() >= deadline {
+                return shapes;
+            }
+        }
+    }
+
+    // Restores a session from the bytes returned by export_state.
+    pub fn from_state(bytes: &[u8]) -> Result<Self, String> {
+        let body = bytes
+            .strip_prefix(STATE_MAGIC)
+            .ok_or_else(|| "not a session state".to_string())?;
+        let (&version, body) = body.split_first().ok_or_else(|| "state is truncated".to_string())?;
+        if version != STATE_VERSION {
+            return Err(format!("unsupported state version {}", version));
+        }
+
+        let state: SessionState = bincode::deserialize(body).map_err(|e| e.to_string())?;
+        state.options.validate()?;
+        // The state may come from anywhere, so its target is held to the same limits as any
+        // other image, and to the working size it claims to have been scaled to. from_image never
+        // exceeds that size, unless a very narrow image is kept one pixel wide.
+        let target_img =
+            decode_image_with_options(&state.target_png, &state.options.decode_options())
+                .map_err(|e| e.to_string())?;
+        let (width, height) = target_img.dimensions();
+        let num_pixels = u64::from(width) * u64::from(height);
+        if num_pixels > u64::from(state.options.working_pixels) && cmp::min(width, height) > 1 {
+            return Err("target is larger than the working size".to_string());
+        }
+        if state.shapes.len() > MAX_STATE_SHAPES {
+            return Err(format!("state has more than {} shapes", MAX_STATE_SHAPES));
+        }
+        // Only shapes this session could have produced are drawn, which also keeps the drawing
+        // arithmetic within range.
+        let shape_options = state.options.shape_options(&target_img);
+        let (min_radius, max_radius) = shape_options.radius_range(width, height);
+        let (x_range, y_range) = (0..width as i32, 0..height as i32);
+        for shape in &state.shapes {
+            if (shape.imgx, shape.imgy) != (width, height) {
+                return Err("shapes do not match the size of the target".to_string());
+            }
+            if !(min_radius..=max_radius).contains(&shape.radius)
+                || !x_range.contains(&shape.center.0)
+                || !y_range.contains(&shape.center.1)
+            {
+                return Err(format!(
+                    "shape at {:?} with radius {} is outside the target or radius bounds",
+                    shape.center, shape.radius
+                ));
+            }
+        }
+
+        let mut session = Self {
+            current_img: RgbaImage::new(width, height),
+            shape_options,
+            target_img,
+            score: Score::default(),
+            options: state.options,
+            shapes: state.shapes,
+            rng: state.rng,
+        };
+        session.redraw();
+        Ok(session)
+    }
+
+    // Draws all shapes onto a blank image and scores it. Each shape only redraws the part of the
+    // image it covers.
+    fn redraw(&mut self) {
+        let (width, height) = self.target_img.dimensions();
+        self.current_img = RgbaImage::new(width, height);
+        for shape in &self.shapes {
+            if let Some(bounds) = shape.get_bounds() {
+                let drawn = shape.draw_subimage(&self.current_img);
+                imageops::replace(
+                    &mut self.current_img,
+                    &drawn,
+                    i64::from(bounds.x),
+                    i64::from(bounds.y),
+                );
+            }
+        }
+        self.score = image_diff(&self.target_img, &self.current_img);
+    }
+
+    #[must_use]
+    pub fn current_score(&self) -> Score {
+        self.score
+    }
+
+    #[must_use]
+    pub fn accepted_shapes(&self) -> &[RandomCircle] {
+        &self.shapes
+    }
+
+    #[must_use]
+    pub fn error_heatmap(&self) -> RgbaImage {
+        error_heatmap(&self.target_img, &self.current_img)
+    }
+
+    #[must_use]
+    pub fn region_diffs(&self, columns: u32, rows: u32) -> Vec<RegionDiff> {
+        region_diffs(&self.target_img, &self.current_img, columns, rows)
+    }
+}
+
+#[wasm_bindgen]
+impl Session {
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        buffer: &ArrayBuffer,
+        options: Option<SessionOptionsObject>,
+    ) -> Result<Session, JsValue> {
+        utils::set_panic_hook();
+
+        let options = SessionOptions::from_js(options)?;
+        let target_img = web::load_image_from_buffer(buffer, &options.decode_options())?;
+        Ok(Self::from_image(target_img, options))
+    }
+
+    #[wasm_bindgen(js_name = fromUrl)]
+    pub async fn from_url(
+        url: String,
+        options: Option<SessionOptionsObject>,
+    ) -> Result<Session, JsValue> {
+        utils::set_panic_hook();
+
+        let options = SessionOptions::from_js(options)?;
+        let target_img = web::load_image(&url, &options.decode_options()).await?;
+        Ok(Self::from_image(target_img, options))
+    }
+
+    #[wasm_bindgen(js_name = fromBlob)]
+    pub async fn from_blob(
+        blob: Blob,
+        options: Option<SessionOptionsObject>,
+    ) -> Result<Session, JsValue> {
+        utils::set_panic_hook();
+
+        let options = SessionOptions::from_js(options)?;
+        let target_img = web::load_image_from_blob(&blob, &options.decode_options()).await?;
+        Ok(Self::from_image(target_img, options))
+    }
+
+    // Uses already decoded pixels, for example from a canvas or a video frame.
+    #[wasm_bindgen(js_name = fromImageData)]
+    pub fn from_image_data(
+        image_data: &ImageData,
+        options: Option<SessionOptionsObject>,
+    ) -> Result<Session, JsValue> {
+        Self::from_pixels(image_data.data(), image_data.width(), image_data.height(), options)
+    }
+
+    // Takes unpremultiplied RGBA pixels, row by row.
+    #[wasm_bindgen(js_name = fromPixels)]
+    pub fn from_pixels(
+        pixels: Clamped<Vec<u8>>,
+        width: u32,
+        height: u32,
+        options: Option<SessionOptionsObject>,
+    ) -> Result<Session, JsValue> {
+        utils::set_panic_hook();
+
+        let options = SessionOptions::from_js(options)?;
+        let target_img = web::image_from_pixels(pixels.0, width, height)?;
+        Ok(Self::from_image(target_img, options))
+    }
+
+    // Runs a single epoch, drawing up to `shapesPerEpoch` non-overlapping shapes. Returns the
+    // accepted circles, which is empty if no shape improved the image.
+    pub fn step(&mut self) -> Result<CircleArray, JsValue> {
+        let shapes = self.step_shapes();
+        to_circle_array(&shapes)
+    }
+
+    // Runs epochs until `budget_ms` milliseconds have passed, and returns all circles accepted
+    // in the meantime. At least one epoch is always run, so the budget is exceeded by up to the
+    // duration of one epoch.
+    #[wasm_bindgen(js_name = runFor)]
+    pub fn run_for(&mut self, budget_ms: f64) -> Result<CircleArray, JsValue> {
+        let shapes = self.run_until(web::now_ms() + budget_ms, web::now_ms);
+        to_circle_array(&shapes)
+    }
+
+    // Returns every circle accepted so far, in drawing order.
+    pub fn shapes(&self) -> Result<CircleArray, JsValue> {
+        to_circle_array(&self.shapes)
+    }
+
+    // The total difference between the target and the current image. Lower is better.
+    pub fn score(&self) -> f64 {
+        self.score.0 as f64
+    }
+
+    // Discards all shapes and starts again from a blank image, keeping the target and options.
+    // A seeded session starts its random sequence again too, so it repeats the same shapes.
+    pub fn reset(&mut self) {
+        self.rng = self.options.rng();
+        self.truncate(0);
+    }
+
+    // Keeps only the first `num_shapes` shapes and redraws the image from them, so evolution
+    // continues from that point. Does nothing if there are not more shapes than that.
+    pub fn truncate(&mut self, num_shapes: usize) {
+        if num_shapes >= self.shapes.len() {
+            return;
+        }
+        self.shapes.truncate(num_shapes);
+        self.redraw();
+    }
+
+    // Returns the complete state of the session as bytes, to be restored with importState. A
+    // restored session accepts exactly the shapes this one would. Its score is recomputed from
+    // the image, so any drift in the running score is gone.
+    #[wasm_bindgen(js_name = exportState)]
+    pub fn export_state(&self) -> Vec<u8> {
+        let (width, height) = self.target_img.dimensions();
+        let mut target_png = Vec::new();
+        PngEncoder::new(&mut target_png)
+            .write_image(self.target_img.as_raw(), width, height, ColorType::Rgba8)
+            .expect("encoding to memory cannot fail");
+        let state = SessionState {
+            options: self.options.clone(),
+            target_png,
+            shapes: self.shapes.clone(),
+            rng: self.rng.clone(),
+        };
+
+        let mut bytes = STATE_MAGIC.to_vec();
+        bytes.push(STATE_VERSION);
+        bincode::serialize_into(&mut bytes, &state).expect("session state is serializable");
+        bytes
+    }
+
+    #[wasm_bindgen(js_name = importState)]
+    pub fn import_state(bytes: &[u8]) -> Result<Session, JsValue> {
+        utils::set_panic_hook();
+
+        Self::from_state(bytes)
+            .map_err(|e| JsValue::from_str(&format!("Invalid session state: {}", e)))
+    }
+
+    // The size of the scaled-down target image, which the coordinates of all circles refer to.
+    #[wasm_bindgen(getter)]
+    pub fn width(&self) -> u32 {
+        self.target_img.width()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn height(&self) -> u32 {
+        self.target_img.height()
+    }
+
+    // The scaled-down target image that circles are compared against.
+    #[wasm_bindgen(js_name = targetImageData)]
+    pub fn target_image_data(&self) -> Result<ImageData, JsValue> {
+        to_image_data(&self.target_img)
+    }
+
+    // The approximation drawn from the accepted circles.
+    #[wasm_bindgen(js_name = currentImageData)]
+    pub fn current_image_data(&self) -> Result<ImageData, JsValue> {
+        to_image_data(&self.current_img)
+    }
+
+    // How far each pixel of the approximation is from the target (see error_heatmap).
+    #[wasm_bindgen(js_name = errorImageData)]
+    pub fn error_image_data(&self) -> Result<ImageData, JsValue> {
+        to_image_data(&self.error_heatmap())
+    }
+
+    // The error within each cell of a grid over the image, row by row. Each score is the part
+    // of score() that falls inside its region.
+    #[wasm_bindgen(js_name = regionErrors)]
+    pub fn region_errors(&self, columns: u32, rows: u32) -> Result<RegionDiffArray, JsValue> {
+        Ok(serde_wasm_bindgen::to_value(&self.region_diffs(columns, rows))?.unchecked_into())
+    }
+}
+
+fn to_image_data(img: &RgbaImage) -> Result<ImageData, JsValue> {
+    let (width, height) = img.dimensions();
+    ImageData::new_with_u8_clamped_array_and_sh(Clamped(img.as_raw()), width, height)
+}
+
+fn to_circle_array(shapes: &[RandomCircle]) -> Result<CircleArray, JsValue> {
+    Ok(serde_wasm_bindgen::to_value(shapes)?.unchecked_into())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::session::{ColorModeName, Session, SessionOptions, SessionState, SizeScheduleName};
+    use crate::session::{MAX_STATE_SHAPES, STATE_MAGIC, STATE_VERSION};
+    use image::{Rgba, RgbaImage};
+    use shape_evolution::image_diff::image_diff;
+    use shape_evolution::palette::ColorMode;
+    use shape_evolution::score::Score;
+    use std::cell::Cell;
+
+    fn target() -> RgbaImage {
+        RgbaImage::from_fn(40, 30, |x, _y| {
+            if x < 20 {
+                Rgba([255, 0, 0, 255])
+            } else {
+                Rgba([0, 0, 255, 255])
+            }
+        })
+    }
+
+    #[test]
+    fn test_step_and_reset() {
+        let options = SessionOptions {
+            working_pixels: 30 * 20,
+            generation_size: 20,
+            num_gens: 5,
+            ..SessionOptions::default()
+        };
+        let mut session = Session::from_image(target(), options);
+        let initial_score = session.current_score();
+
+        let mut accepted = 0;
+        for _ in 0..5 {
+            accepted += session.step_shapes().len();
+        }
+        assert!(accepted > 0);
+        assert_eq!(session.accepted_shapes().len(), accepted);
+        assert!(session.current_score() < initial_score);
+
+        session.reset();
+        assert!(session.accepted_shapes().is_empty());
+        assert_eq!(session.current_score(), initial_score);
+    }
+
+    #[test]
+    fn test_seed_is_reproducible() {
+        let options = SessionOptions {
+            working_pixels: 30 * 20,
+            generation_size: 20,
+            num_gens: 5,
+            seed: Some(7),
+            ..SessionOptions::default()
+        };
+        let mut a = Session::from_image(target(), options.clone());
+        let mut b = Session::from_image(target(), options);
+        for _ in 0..5 {
+            assert_eq!(a.step_shapes(), b.step_shapes());
+        }
+        assert_eq!(a.current_score(), b.current_score());
+
+        let shapes = a.accepted_shapes().to_vec();
+        a.reset();
+        for _ in 0..5 {
+            a.step_shapes();
+        }
+        assert_eq!(a.accepted_shapes(), shapes);
+    }
+
+    #[test]
+    fn test_run_until() {
+        let options = SessionOptions {
+            working_pixels: 30 * 20,
+            generation_size: 20,
+            num_gens: 5,
+            shapes_per_epoch: 3,
+            ..SessionOptions::default()
+        };
+        let mut session = Session::from_image(target(), options);
+        let clock = Cell::new(0.0);
+        let now = || {
+            clock.set(clock.get() + 1.0);
+            clock.get()
+        };
+
+        // One epoch is run even when the deadline has already passed.
+        let shapes = session.run_until(0.0, now);
+        assert_eq!(clock.get(), 1.0);
+        assert!(shapes.len() <= 3);
+
+        let more_shapes = session.run_until(5.0, now);
+        assert_eq!(clock.get(), 5.0);
+        assert_eq!(session.accepted_shapes().len(), shapes.len() + more_shapes.len());
+    }
+
+    #[test]
+    fn test_truncate() {
+        let options = SessionOptions {
+            working_pixels: 30 * 20,
+            generation_size: 20,
+            num_gens: 5,
+            ..SessionOptions::default()
+        };
+        let mut session = Session::from_image(target(), options);
+        let mut snapshots = vec![(session.current_img.clone(), session.current_score())];
+        while session.accepted_shapes().len() < 4 {
+            if !session.step_shapes().is_empty() {
+                snapshots.push((session.current_img.clone(), session.current_score()));
+            }
+        }
+        let shapes = session.accepted_shapes().to_vec();
+
+        session.truncate(10);
+        assert_eq!(session.accepted_shapes(), shapes);
+
+        session.truncate(2);
+        assert_eq!(session.accepted_shapes(), &shapes[..2]);
+        assert_eq!(session.current_img, snapshots[2].0);
+        assert_eq!(session.current_score(), image_diff(&session.target_img, &snapshots[2].0));
+
+        session.truncate(0);
+        assert!(session.accepted_shapes().is_empty());
+        assert_eq!(session.current_score(), snapshots[0].1);
+    }
+
+    #[test]
+    fn test_error_views() {
+        let options = SessionOptions {
+            working_pixels: 30 * 20,
+            generation_size: 20,
+            num_gens: 5,
+            seed: Some(7),
+            ..SessionOptions::default()
+        };
+        let mut session = Session::from_image(target(), options);
+        for _ in 0..100 {
+            if session.accepted_shapes().len() >= 3 {
+                break;
+            }
+            session.step_shapes();
+        }
+        assert!(session.accepted_shapes().len() >= 3);
+
+        assert_eq!(session.error_heatmap().dimensions(), session.target_img.dimensions());
+        let regions = session.region_diffs(4, 3);
+        assert_eq!(regions.len(), 12);
+        let total: u64 = regions.iter().map(|region| region.score.0).sum();
+        assert_eq!(Score(total), image_diff(&session.target_img, &session.current_img));
+    }
+
+    #[test]
+    fn test_state_round_trip() {
+        let options = SessionOptions {
+            working_pixels: 30 * 20,
+            generation_size: 20,
+            num_gens: 5,
+            color_mode: ColorModeName::Palette,
+            palette_size: 4,
+            ..SessionOptions::default()
+        };
+        let mut session = Session::from_image(target(), options);
+        for _ in 0..5 {
+            session.step_shapes();
+        }
+
+        let mut restored = Session::from_state(&session.export_state()).unwrap();
+        assert_eq!(restored.target_img, session.target_img);
+        assert_eq!(restored.current_img, session.current_img);
+        // The score is recomputed, so it is exact even if the running score had drifted.
+        assert_eq!(
+            restored.current_score(),
+            image_diff(&session.target_img, &session.current_img)
+        );
+        assert_eq!(restored.options, session.options);
+        for _ in 0..5 {
+            assert_eq!(restored.step_shapes(), session.step_shapes());
+        }
+
+        let mut state = session.export_state();
+        assert!(Session::from_state(&state[..state.len() - 1]).is_err());
+        state[4] = 3;
+        assert_eq!(
+            Session::from_state(&state).err(),
+            Some("unsupported state version 3".to_string())
+        );
+        assert!(Session::from_state(b"PNG").is_err());
+    }
+
+    // Serializes a state the way export_state does.
+    fn encode_state(state: &SessionState) -> Vec<u8> {
+        let mut bytes = STATE_MAGIC.to_vec();
+        bytes.push(STATE_VERSION);
+        bincode::serialize_into(&mut bytes, state).unwrap();
+        bytes
+    }
+
+    fn decode_state(bytes: &[u8]) -> SessionState {
+        bincode::deserialize(&bytes[STATE_MAGIC.len() + 1..]).unwrap()
+    }
+
+    #[test]
+    fn test_state_shapes_must_be_valid() {
+        let options = SessionOptions {
+            working_pixels: 30 * 20,
+            generation_size: 20,
+            num_gens: 5,
+            seed: Some(7),
+            ..SessionOptions::default()
+        };
+        let mut session = Session::from_image(target(), options);
+        for _ in 0..100 {
+            if !session.accepted_shapes().is_empty() {
+                break;
+            }
+            session.step_shapes();
+        }
+        let state = decode_state(&session.export_state());
+        assert!(!state.shapes.is_empty());
+
+        let mut tampered = decode_state(&session.export_state());
+        tampered.shapes[0].radius = i32::MAX;
+        let error = Session::from_state(&encode_state(&tampered)).err().unwrap();
+        assert!(error.contains("radius"), "{}", error);
+
+        let mut tampered = decode_state(&session.export_state());
+        tampered.shapes[0].center = (-1, 0);
+        assert!(Session::from_state(&encode_state(&tampered)).is_err());
+
+        let mut tampered = decode_state(&session.export_state());
+        tampered.shapes = vec![state.shapes[0].clone(); MAX_STATE_SHAPES + 1];
+        assert!(Session::from_state(&encode_state(&tampered)).is_err());
+    }
+
+    #[test]
+    fn test_state_target_must_fit_working_size() {
+        let options = SessionOptions {
+            working_pixels: 40 * 30,
+            ..SessionOptions::default()
+        };
+        let session = Session::from_image(target(), options);
+
+        // Claim a smaller working size than the stored target was scaled to.
+        let mut tampered = decode_state(&session.export_state());
+        tampered.options.working_pixels = 100;
+        assert_eq!(
+            Session::from_state(&encode_state(&tampered)).err(),
+            Some("target is larger than the working size".to_string())
+        );
+    }
+
+    #[test]
+    fn test_validate() {
+        assert_eq!(SessionOptions::default().validate(), Ok(()));
+
+        let options = SessionOptions {
+            generation_size: 0,
+            ..SessionOptions::default()
+        };
+        assert_eq!(options.validate(), Err("generationSize must be at least 1".to_string()));
+
+        let options = SessionOptions {
+            min_radius: 5,
+            max_radius: Some(4),
+            ..SessionOptions::default()
+        };
+        assert!(options.validate().is_err());
+
+        let options = SessionOptions {
+            size_schedule: SizeScheduleName::Exponential,
+            schedule_length: 0,
+            ..SessionOptions::default()
+        };
+        assert!(options.validate().is_err());
+    }
+
+    #[test]
+    fn test_shape_options() {
+        let options = SessionOptions {
+            color_mode: ColorModeName::Palette,
+            palette_size: 2,
+            max_radius: Some(10),
+            ..SessionOptions::default()
+        };
+        let shape_options = options.shape_options(&target());
+        assert!(matches!(shape_options.color_mode, ColorMode::Palette(_)));
+        assert_eq!(shape_options.max_radius, Some(10));
+    }
+}
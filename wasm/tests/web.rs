@@ -0,0 +1,23 @@
+// Browser tests, run with `wasm-pack test --headless --firefox wasm`.
+#![cfg(target_arch = "wasm32")]
+
+use shape_evolution::decode::DecodeOptions;
+use wasm::web::{image_from_pixels, load_image};
+
+wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+#[wasm_bindgen_test::wasm_bindgen_test]
+pub async fn load_png() {
+    let url = "https://upload.wikimedia.org/wikipedia/commons/thumb/4/47/PNG_transparency_demonstration_1.png/420px-PNG_transparency_demonstration_1.png";
+    let image = load_image(url, &DecodeOptions::default()).await.unwrap();
+    assert_eq!(image.width(), 420);
+    assert_eq!(image.height(), 315);
+}
+
+#[wasm_bindgen_test::wasm_bindgen_test]
+pub fn pixels_must_match_size() {
+    let image = image_from_pixels(vec![255; 2 * 3 * 4], 2, 3).unwrap();
+    assert_eq!(image.dimensions(), (2, 3));
+    assert!(image_from_pixels(vec![255; 2 * 3 * 4 - 1], 2, 3).is_err());
+    assert!(image_from_pixels(Vec::new(), 0, 0).is_err());
+}